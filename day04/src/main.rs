@@ -1,55 +1,322 @@
+use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-#[derive(Clone)]
 struct ScratchCard {
+    id: usize,
     winning_numbers: Vec<i32>,
     numbers: Vec<i32>,
-    num_earned: i32,
+}
+
+impl ScratchCard {
+    fn new(line: &str) -> Result<Self, CardError> {
+        let malformed = || CardError::Malformed {
+            line: line.to_string(),
+        };
+        let (label, nums_str) = line.split_once(": ").ok_or_else(malformed)?;
+        let id = label
+            .strip_prefix("Card")
+            .and_then(|id_str| id_str.trim().parse::<usize>().ok())
+            .ok_or_else(malformed)?;
+        let (first_nums_str, second_nums_str) = nums_str.split_once(" | ").ok_or_else(malformed)?;
+        let parse_nums = |nums_str: &str| -> Result<Vec<i32>, CardError> {
+            let mut seen: HashSet<i32> = HashSet::new();
+            let mut nums: Vec<i32> = Vec::new();
+            for num_str in nums_str.split_whitespace() {
+                let num = num_str.parse::<i32>().map_err(|_| malformed())?;
+                if !seen.insert(num) {
+                    return Err(CardError::RepeatedNumber {
+                        card_id: id,
+                        number: num,
+                    });
+                }
+                nums.push(num);
+            }
+            Ok(nums)
+        };
+        Ok(ScratchCard {
+            id,
+            winning_numbers: parse_nums(first_nums_str)?,
+            numbers: parse_nums(second_nums_str)?,
+        })
+    }
+
+    fn num_matches(&self) -> usize {
+        self.numbers
+            .iter()
+            .filter(|num| self.winning_numbers.contains(num))
+            .count()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum CardError {
+    Malformed { line: String },
+    // the same number shows up twice among a card's winning numbers, or twice among its numbers
+    RepeatedNumber { card_id: usize, number: i32 },
+    Duplicate { card_id: usize },
+    Gap { expected_id: usize, found_id: usize },
+    Overflow { card_id: usize },
+}
+
+impl fmt::Display for CardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CardError::Malformed { line } => write!(f, "could not parse card from line '{line}'"),
+            CardError::RepeatedNumber { card_id, number } => {
+                write!(f, "card {card_id} lists the number {number} more than once")
+            }
+            CardError::Duplicate { card_id } => write!(f, "card {card_id} appears more than once"),
+            CardError::Gap {
+                expected_id,
+                found_id,
+            } => write!(f, "expected card {expected_id} but found card {found_id}"),
+            CardError::Overflow { card_id } => {
+                write!(
+                    f,
+                    "card copy count overflowed while processing card {card_id}"
+                )
+            }
+        }
+    }
+}
+
+// Checks that a card has the next id in the sequence 1, 2, 3... Anything lower than expected
+// must already have been seen, so it's reported as a duplicate.
+fn check_card_id(card_id: usize, expected_id: usize) -> Result<(), CardError> {
+    if card_id < expected_id {
+        Err(CardError::Duplicate { card_id })
+    } else if card_id > expected_id {
+        Err(CardError::Gap {
+            expected_id,
+            found_id: card_id,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+// Parses every line into a card, then sorts them by id and checks that the ids run from 1 with
+// no gaps or duplicates.
+fn parse_cards<I, S>(lines: I) -> Result<Vec<ScratchCard>, CardError>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut cards = lines
+        .into_iter()
+        .map(|line| ScratchCard::new(line.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    cards.sort_by_key(|card| card.id);
+    for (i, card) in cards.iter().enumerate() {
+        check_card_id(card.id, i + 1)?;
+    }
+    Ok(cards)
+}
+
+// Unsigned integer types that card copy counts can be tallied in. Everything goes through
+// checked arithmetic, so an overflow is reported rather than silently wrapping.
+trait CopyCount: Copy {
+    const ZERO: Self;
+    const ONE: Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_copy_count {
+    ($($t:ty),*) => {
+        $(
+            impl CopyCount for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_copy_count!(u64, u128);
+
+// Expects cards as returned by `parse_cards`, i.e. sorted with contiguous ids starting at 1.
+fn solve_part_2(cards: &[ScratchCard]) -> Result<u64, CardError> {
+    let mut nums_earned = vec![1_u64; cards.len()];
+    for (i, card) in cards.iter().enumerate() {
+        let overflow = CardError::Overflow { card_id: card.id };
+        let num_earned = nums_earned[i];
+        let last_index = (i + card.num_matches()).min(cards.len() - 1);
+        for earned in nums_earned[i + 1..=last_index].iter_mut() {
+            *earned = earned.checked_add(num_earned).ok_or(overflow.clone())?;
+        }
+    }
+    cards
+        .iter()
+        .zip(nums_earned)
+        .try_fold(0_u64, |total, (card, num_earned)| {
+            total
+                .checked_add(num_earned)
+                .ok_or(CardError::Overflow { card_id: card.id })
+        })
+}
+
+// Copies that stop applying were counted in the running total by an earlier card, so taking
+// them off first can't underflow, and means we only overflow if the new count really does.
+fn roll_running_copies<T: CopyCount>(running_copies: T, starting: T, stopping: T) -> Option<T> {
+    running_copies
+        .checked_sub(stopping)
+        .and_then(|copies| copies.checked_add(starting))
+}
+
+// Counts the total number of cards (originals plus copies) without ever holding more than one
+// card in memory. Rather than a per-card tally of copies earned, we keep a rolling difference
+// buffer: slot `k` holds the copy counts that start and stop applying `k + 1` cards from now.
+// A card can only reach as many cards ahead as it has numbers, so the buffer stays small.
+// Since cards can't be sorted in this mode, they must already be in order.
+fn solve_part_2_streaming<T, I, S>(lines: I) -> Result<T, CardError>
+where
+    T: CopyCount,
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut pending: VecDeque<(T, T)> = VecDeque::new();
+    let mut running_copies = T::ZERO;
+    let mut total = T::ZERO;
+    for (i, line) in lines.into_iter().enumerate() {
+        let card = ScratchCard::new(line.as_ref())?;
+        check_card_id(card.id, i + 1)?;
+        let overflow = || CardError::Overflow { card_id: card.id };
+
+        let (starting, stopping) = pending.pop_front().unwrap_or((T::ZERO, T::ZERO));
+        running_copies =
+            roll_running_copies(running_copies, starting, stopping).ok_or_else(overflow)?;
+        let num_earned = running_copies.checked_add(T::ONE).ok_or_else(overflow)?;
+        total = total.checked_add(num_earned).ok_or_else(overflow)?;
+
+        let num_matches = card.num_matches();
+        if num_matches > 0 {
+            if pending.len() <= num_matches {
+                pending.resize(num_matches + 1, (T::ZERO, T::ZERO));
+            }
+            pending[0].0 = pending[0].0.checked_add(num_earned).ok_or_else(overflow)?;
+            pending[num_matches].1 = pending[num_matches]
+                .1
+                .checked_add(num_earned)
+                .ok_or_else(overflow)?;
+        }
+    }
+    Ok(total)
+}
+
+struct CardExplanation {
+    card_id: usize,
+    num_earned: u64,
+    // (id of the earlier card, number of copies it handed out to this card)
+    copies_from: Vec<(usize, u64)>,
+}
+
+// Like `solve_part_2`, but records where every card's copies came from.
+fn explain_cards(cards: &[ScratchCard]) -> Result<Vec<CardExplanation>, CardError> {
+    let mut explanations = cards
+        .iter()
+        .map(|card| CardExplanation {
+            card_id: card.id,
+            num_earned: 1,
+            copies_from: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    for (i, card) in cards.iter().enumerate() {
+        let num_earned = explanations[i].num_earned;
+        let last_index = (i + card.num_matches()).min(cards.len() - 1);
+        for explanation in explanations[i + 1..=last_index].iter_mut() {
+            explanation.num_earned = explanation
+                .num_earned
+                .checked_add(num_earned)
+                .ok_or(CardError::Overflow { card_id: card.id })?;
+            explanation.copies_from.push((card.id, num_earned));
+        }
+    }
+    Ok(explanations)
+}
+
+fn display_explanations(explanations: &[CardExplanation]) -> String {
+    let mut output = String::new();
+    for explanation in explanations {
+        let plural = if explanation.num_earned == 1 {
+            "y"
+        } else {
+            "ies"
+        };
+        output.push_str(&format!(
+            "Card {}: {} cop{plural}\n",
+            explanation.card_id, explanation.num_earned
+        ));
+        output.push_str("  1 original\n");
+        for (source_id, num_copies) in explanation.copies_from.iter() {
+            output.push_str(&format!("  {num_copies} from card {source_id}\n"));
+        }
+    }
+    output
+}
+
+// Lazily generates `num_cards` card lines, each of which has exactly `matches_per_card`
+// winning numbers (capped at 10).
+fn generate_cards(num_cards: usize, matches_per_card: usize) -> impl Iterator<Item = String> {
+    let matches_per_card = matches_per_card.min(10);
+    let winning_numbers = (1..=10).map(|n| n.to_string()).collect::<Vec<_>>();
+    let numbers = (1..=matches_per_card)
+        .chain(11..=20 - matches_per_card)
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>();
+    let line_suffix = format!(": {} | {}", winning_numbers.join(" "), numbers.join(" "));
+    (1..=num_cards).map(move |id| format!("Card {id}{line_suffix}"))
 }
 
 fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() > 1 && args[1] == "--generate" {
+        let num_cards = args[2].parse::<usize>().unwrap();
+        let matches_per_card = args[3].parse::<usize>().unwrap();
+        match solve_part_2_streaming::<u128, _, _>(generate_cards(num_cards, matches_per_card)) {
+            Ok(total) => println!("Total cards: {total}"),
+            Err(err) => println!("Error: {err}"),
+        }
+        return;
+    }
+
     let file = File::open("resources/input_1").unwrap();
-    let mut cards: Vec<ScratchCard> = Vec::new();
     let reader = BufReader::new(file);
-    for line in reader.lines() {
-        let line_content = &line.unwrap();
-        let halves = line_content.split(" | ").collect::<Vec<_>>();
-        let first_nums_str = halves[0].split(": ").collect::<Vec<_>>()[1];
-        let first_nums = first_nums_str
-            .split_whitespace()
-            .map(|num| num.parse::<i32>().unwrap())
-            .collect::<Vec<_>>();
-        let second_nums = halves[1]
-            .split_whitespace()
-            .map(|num| num.parse::<i32>().unwrap())
-            .collect::<Vec<_>>();
-        cards.push(ScratchCard {
-            winning_numbers: first_nums,
-            numbers: second_nums,
-            num_earned: 1,
-        })
+    if args.len() > 1 && args[1] == "--stream" {
+        let lines = reader.lines().map(|line| line.unwrap());
+        match solve_part_2_streaming::<u64, _, _>(lines) {
+            Ok(total) => println!("Part 2 solution: {total}"),
+            Err(err) => println!("Error: {err}"),
+        }
+        return;
     }
 
-    // don't panic on empty list
-    let max_card_index = if cards.is_empty() { 0 } else { cards.len() - 1 };
-    for i in 0..cards.len() {
-        let card = cards[i].clone();
-        let mut num_matches = 0;
-        for num in card.numbers.iter() {
-            if card.winning_numbers.contains(num) {
-                num_matches += 1;
-            }
+    let cards = match parse_cards(reader.lines().map(|line| line.unwrap())) {
+        Ok(cards) => cards,
+        Err(err) => {
+            println!("Error: {err}");
+            return;
         }
+    };
 
-        let mut working_index = i + 1;
-        while num_matches > 0 && working_index <= max_card_index {
-            cards[working_index].num_earned += card.num_earned;
-            working_index += 1;
-            num_matches -= 1;
+    if args.len() > 1 && args[1] == "--explain" {
+        match explain_cards(&cards) {
+            Ok(explanations) => print!("{}", display_explanations(&explanations)),
+            Err(err) => println!("Error: {err}"),
         }
+        return;
     }
-    let part_2_solution = cards.iter().map(|card| card.num_earned).sum::<i32>();
+
+    let part_2_solution = solve_part_2(&cards).unwrap();
     println!("Part 2 solution: {part_2_solution}");
     // let mut scores: Vec<i32> = Vec::new();
     // for card in cards.iter() {
@@ -70,3 +337,126 @@ fn main() {
     // let part_1_solution = scores.iter().sum::<i32>();
     // println!("Part 1 solution: {part_1_solution}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_lines(file_path: &str) -> Vec<String> {
+        let file = File::open(file_path).unwrap();
+        BufReader::new(file).lines().map(|l| l.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_part_two() {
+        let cards = parse_cards(read_lines("resources/input_1")).unwrap();
+        assert_eq!(solve_part_2(&cards), Ok(5554894));
+    }
+
+    #[test]
+    fn test_part_two_streaming() {
+        let sample = read_lines("resources/sample_1");
+        assert_eq!(solve_part_2_streaming::<u64, _, _>(&sample), Ok(30));
+        let input = read_lines("resources/input_1");
+        assert_eq!(solve_part_2_streaming::<u64, _, _>(&input), Ok(5554894));
+    }
+
+    #[test]
+    fn test_streaming_many_cards() {
+        // with one match per card, card n ends up with n copies
+        let num_cards: u64 = 10_000;
+        let total = solve_part_2_streaming::<u64, _, _>(generate_cards(num_cards as usize, 1));
+        assert_eq!(total, Ok(num_cards * (num_cards + 1) / 2));
+    }
+
+    #[test]
+    fn test_streaming_reports_overflow() {
+        // two matches per card makes copy counts grow like the Fibonacci sequence
+        let u64_result = solve_part_2_streaming::<u64, _, _>(generate_cards(1_000, 2));
+        let Err(CardError::Overflow {
+            card_id: u64_card_id,
+        }) = u64_result
+        else {
+            panic!("Expected u64 count to overflow");
+        };
+        let u128_result = solve_part_2_streaming::<u128, _, _>(generate_cards(1_000, 2));
+        let Err(CardError::Overflow {
+            card_id: u128_card_id,
+        }) = u128_result
+        else {
+            panic!("Expected u128 count to overflow");
+        };
+        assert!(u64_card_id < u128_card_id);
+
+        let cards = parse_cards(generate_cards(1_000, 2)).unwrap();
+        assert!(solve_part_2(&cards).is_err());
+    }
+
+    #[test]
+    fn test_running_copies_near_max() {
+        // the old and new copies together would overflow, but the count after the swap fits
+        assert_eq!(
+            roll_running_copies(u64::MAX - 1, 15, u64::MAX - 20),
+            Some(34)
+        );
+        assert_eq!(
+            roll_running_copies(u64::MAX, u64::MAX, u64::MAX),
+            Some(u64::MAX)
+        );
+        assert_eq!(roll_running_copies(u64::MAX - 1, 2, 0), None);
+    }
+
+    #[test]
+    fn test_card_validation() {
+        let out_of_order = ["Card 2: 1 2 | 3 4", "Card 1: 1 2 | 1 3"];
+        assert_eq!(parse_cards(out_of_order).unwrap()[0].id, 1);
+        assert_eq!(
+            solve_part_2_streaming::<u64, _, _>(out_of_order),
+            Err(CardError::Gap {
+                expected_id: 1,
+                found_id: 2
+            })
+        );
+
+        let gap = ["Card 1: 1 2 | 3 4", "Card 3: 1 2 | 1 3"];
+        assert_eq!(
+            parse_cards(gap).err(),
+            Some(CardError::Gap {
+                expected_id: 2,
+                found_id: 3
+            })
+        );
+
+        let duplicate = [
+            "Card 1: 1 2 | 3 4",
+            "Card 2: 1 2 | 3 4",
+            "Card 2: 1 2 | 1 3",
+        ];
+        assert_eq!(
+            parse_cards(duplicate).err(),
+            Some(CardError::Duplicate { card_id: 2 })
+        );
+
+        let repeated_number = ["Card 1: 1 2 | 3 4 3"];
+        assert_eq!(
+            parse_cards(repeated_number).err(),
+            Some(CardError::RepeatedNumber {
+                card_id: 1,
+                number: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_explain_cards() {
+        let cards = parse_cards(read_lines("resources/sample_1")).unwrap();
+        let explanations = explain_cards(&cards).unwrap();
+        let card_4 = &explanations[3];
+        assert_eq!(card_4.num_earned, 8);
+        assert_eq!(card_4.copies_from, vec![(1, 1), (2, 2), (3, 4)]);
+        assert_eq!(
+            display_explanations(&explanations[..2]),
+            "Card 1: 1 copy\n  1 original\nCard 2: 2 copies\n  1 original\n  1 from card 1\n"
+        );
+    }
+}
@@ -1,23 +1,14 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
 use std::fs;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum EntryType {
-    Finished, // indicates we're finished searching
-    Seed,
-    Soil,
-    Fertilizer,
-    Water,
-    Light,
-    Temperature,
-    Humidity,
-    Location,
-}
-
 #[derive(Debug, Copy, Clone)]
 struct Range {
     start: i64,
     end: i64,
-    entry_type: EntryType,
+    // index into the reversed almanac's stages; one past the last stage means we're finished
+    stage: usize,
 }
 
 #[derive(Debug)]
@@ -47,26 +38,246 @@ impl RangeMap {
             dest_source_diff,
         }
     }
+
+    // The same mapping, but from destination back to source.
+    fn reversed(&self) -> RangeMap {
+        RangeMap {
+            source_start: self.source_start + self.dest_source_diff,
+            source_end: self.source_end + self.dest_source_diff,
+            dest_source_diff: -self.dest_source_diff,
+        }
+    }
+}
+
+// An inclusive range of values within a single category.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Interval {
+    start: i64,
+    end: i64,
+}
+
+// Sorts intervals and combines any that overlap or sit right next to each other.
+fn merge_intervals(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort();
+    let mut merged: Vec<Interval> = Vec::new();
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if interval.start <= last.end.saturating_add(1) => {
+                last.end = std::cmp::max(last.end, interval.end);
+            }
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+// Splits an interval wherever one stage's (sorted) range maps start or stop, pairing each
+// piece with the diff that stage applies to it. Pieces that no range map covers get None;
+// going forward through a stage, those values keep their number.
+fn split_interval(range_maps: &[RangeMap], interval: Interval) -> Vec<(Interval, Option<i64>)> {
+    let mut pieces: Vec<(Interval, Option<i64>)> = Vec::new();
+    // None once the whole interval has been dealt with
+    let mut remaining_start = Some(interval.start);
+    for range_map in range_maps {
+        let Some(start) = remaining_start else {
+            break;
+        };
+        if range_map.source_end < start {
+            continue;
+        }
+        if range_map.source_start > interval.end {
+            break;
+        }
+        if range_map.source_start > start {
+            let end = range_map.source_start - 1;
+            pieces.push((Interval { start, end }, None));
+        }
+        let overlap_start = std::cmp::max(start, range_map.source_start);
+        let overlap_end = std::cmp::min(interval.end, range_map.source_end);
+        pieces.push((
+            Interval {
+                start: overlap_start,
+                end: overlap_end,
+            },
+            Some(range_map.dest_source_diff),
+        ));
+        remaining_start = if overlap_end < interval.end {
+            Some(overlap_end + 1)
+        } else {
+            None
+        };
+    }
+    if let Some(start) = remaining_start {
+        let end = interval.end;
+        pieces.push((Interval { start, end }, None));
+    }
+    pieces
+}
+
+// The values that two sets of sorted, merged intervals have in common.
+fn intersect_intervals(a: &[Interval], b: &[Interval]) -> Vec<Interval> {
+    let mut intersection: Vec<Interval> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let start = std::cmp::max(a[i].start, b[j].start);
+        let end = std::cmp::min(a[i].end, b[j].end);
+        if start <= end {
+            intersection.push(Interval { start, end });
+        }
+        if a[i].end < b[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    intersection
+}
+
+// Sends each interval through one stage's range maps, splitting it as needed.
+fn propagate_intervals(range_maps: &[RangeMap], intervals: &[Interval]) -> Vec<Interval> {
+    let propagated = intervals
+        .iter()
+        .flat_map(|&interval| split_interval(range_maps, interval))
+        .map(|(piece, diff)| Interval {
+            start: piece.start + diff.unwrap_or(0),
+            end: piece.end + diff.unwrap_or(0),
+        })
+        .collect::<Vec<_>>();
+    merge_intervals(propagated)
+}
+
+// Every stage between two categories folded into one piecewise-linear function. The segments
+// are sorted, don't overlap, and together cover every i64.
+#[derive(Debug)]
+struct ComposedMap {
+    source: String,
+    destination: String,
+    segments: Vec<RangeMap>,
+}
+
+impl ComposedMap {
+    fn compose(source: &str, destination: &str, path: &[&CategoryMap]) -> ComposedMap {
+        let mut segments = vec![RangeMap {
+            source_start: i64::MIN,
+            source_end: i64::MAX,
+            dest_source_diff: 0,
+        }];
+        for category_map in path {
+            let mut next_segments: Vec<RangeMap> = Vec::new();
+            for segment in segments {
+                // split the segment's image under everything composed so far, then shift the
+                // pieces back into source terms
+                let image = Interval {
+                    start: segment.source_start + segment.dest_source_diff,
+                    end: segment.source_end + segment.dest_source_diff,
+                };
+                for (piece, diff) in split_interval(&category_map.range_maps, image) {
+                    let next_segment = RangeMap {
+                        source_start: piece.start - segment.dest_source_diff,
+                        source_end: piece.end - segment.dest_source_diff,
+                        dest_source_diff: segment.dest_source_diff + diff.unwrap_or(0),
+                    };
+                    match next_segments.last_mut() {
+                        Some(last) if last.dest_source_diff == next_segment.dest_source_diff => {
+                            last.source_end = next_segment.source_end;
+                        }
+                        _ => next_segments.push(next_segment),
+                    }
+                }
+            }
+            segments = next_segments;
+        }
+        ComposedMap {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            segments,
+        }
+    }
+
+    // Binary search for the segment holding the value.
+    fn map_value(&self, value: i64) -> i64 {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.source_end < value);
+        value + self.segments[index].dest_source_diff
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = format!("{}_start,{}_end,offset\n", self.source, self.source);
+        for segment in self.segments.iter() {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                segment.source_start, segment.source_end, segment.dest_source_diff
+            ));
+        }
+        csv
+    }
+
+    fn to_json(&self) -> String {
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| {
+                format!(
+                    "    {{\"start\": {}, \"end\": {}, \"offset\": {}}}",
+                    segment.source_start, segment.source_end, segment.dest_source_diff
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut json = String::from("{\n");
+        json.push_str(&format!("  \"source\": {},\n", json_string(&self.source)));
+        json.push_str(&format!(
+            "  \"destination\": {},\n",
+            json_string(&self.destination)
+        ));
+        json.push_str(&format!(
+            "  \"segments\": [\n{}\n  ]\n",
+            segments.join(",\n")
+        ));
+        json.push_str("}\n");
+        json
+    }
+}
+
+// Quotes a string for JSON, escaping anything that would end it early.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
 }
 
 #[derive(Debug)]
 struct ReversedAlmanac {
-    location_to_humidity: Vec<RangeMap>,
-    humidity_to_temperature: Vec<RangeMap>,
-    temperature_to_light: Vec<RangeMap>,
-    light_to_water: Vec<RangeMap>,
-    water_to_fertilizer: Vec<RangeMap>,
-    fertilizer_to_soil: Vec<RangeMap>,
-    soil_to_seed: Vec<RangeMap>,
-    seed_to_finished: Vec<RangeMap>,
+    // Reversed range maps for each stage, ordered from the target category back to the source
+    // category. The final stage maps the source values we're interested in (e.g. seeds) to
+    // 'finished'.
+    stages: Vec<Vec<RangeMap>>,
 }
 
 impl ReversedAlmanac {
+    fn new(reversed_category_maps: &[&CategoryMap], mut source_to_finished: Vec<RangeMap>) -> Self {
+        source_to_finished.sort_by_key(|range_map| range_map.source_start);
+        let mut stages = reversed_category_maps
+            .iter()
+            .map(|category_map| category_map.reversed_range_maps.clone())
+            .collect::<Vec<_>>();
+        stages.push(source_to_finished);
+        ReversedAlmanac { stages }
+    }
+
     fn depth_first_search_by_range(&self) -> Option<i64> {
-        let mut search_stack = self.location_ranges_search_stack();
+        let mut search_stack = self.target_ranges_search_stack();
 
         while let Some(search_range) = search_stack.pop() {
-            if search_range.range.entry_type == EntryType::Finished {
+            if search_range.range.stage == self.stages.len() {
                 return Some(
                     search_range.range.start - search_range.diff_from_originating_location_range,
                 );
@@ -77,18 +288,8 @@ impl ReversedAlmanac {
     }
 
     fn destination_ranges_desc_order(&self, search_range: SearchRange) -> Vec<SearchRange> {
-        use EntryType::*;
-        let (range_maps, destination_type) = match search_range.range.entry_type {
-            Location => (&self.location_to_humidity, Humidity),
-            Humidity => (&self.humidity_to_temperature, Temperature),
-            Temperature => (&self.temperature_to_light, Light),
-            Light => (&self.light_to_water, Water),
-            Water => (&self.water_to_fertilizer, Fertilizer),
-            Fertilizer => (&self.fertilizer_to_soil, Soil),
-            Soil => (&self.soil_to_seed, Seed),
-            Seed => (&self.seed_to_finished, Finished),
-            Finished => panic!("Cannot look up destination ranges for a Finished entry!"),
-        };
+        let range_maps = &self.stages[search_range.range.stage];
+        let destination_stage = search_range.range.stage + 1;
         let mut destination_ranges: Vec<SearchRange> = Vec::new();
         // Detect any overlap between each range map and our search range. Remember:
         // range maps are sorted in ascending order.
@@ -102,7 +303,7 @@ impl ReversedAlmanac {
                         start: std::cmp::max(range_map.source_start, search_range.range.start)
                             + range_map.dest_source_diff,
                         end: range_map.source_end + range_map.dest_source_diff,
-                        entry_type: destination_type,
+                        stage: destination_stage,
                     },
                     diff_from_originating_location_range: search_range
                         .diff_from_originating_location_range
@@ -116,7 +317,7 @@ impl ReversedAlmanac {
                     range: Range {
                         start: search_range.range.start + range_map.dest_source_diff,
                         end: search_range.range.end + range_map.dest_source_diff,
-                        entry_type: destination_type,
+                        stage: destination_stage,
                     },
                     diff_from_originating_location_range: search_range
                         .diff_from_originating_location_range
@@ -131,7 +332,7 @@ impl ReversedAlmanac {
                     range: Range {
                         start: range_map.source_start + range_map.dest_source_diff,
                         end: search_range.range.end + range_map.dest_source_diff,
-                        entry_type: destination_type,
+                        stage: destination_stage,
                     },
                     diff_from_originating_location_range: search_range
                         .diff_from_originating_location_range
@@ -147,19 +348,18 @@ impl ReversedAlmanac {
         destination_ranges
     }
 
-    fn location_ranges_search_stack(&self) -> Vec<SearchRange> {
+    fn target_ranges_search_stack(&self) -> Vec<SearchRange> {
         // We're using a Vec as our stack to power depth-first search, but we want to start
-        // searching from the lowest-numbered location and our original vec was sorted in
-        // ascending order. Reverse to switch it to descending so we can start by popping off
-        // the lowest possible value.
-        let mut search_ranges = self
-            .location_to_humidity
+        // searching from the lowest-numbered target value (e.g. location) and our original vec
+        // was sorted in ascending order. Reverse to switch it to descending so we can start by
+        // popping off the lowest possible value.
+        let mut search_ranges = self.stages[0]
             .iter()
-            .map(|loc_to_hum| SearchRange {
+            .map(|range_map| SearchRange {
                 range: Range {
-                    start: loc_to_hum.source_start,
-                    end: loc_to_hum.source_end,
-                    entry_type: EntryType::Location,
+                    start: range_map.source_start,
+                    end: range_map.source_end,
+                    stage: 0,
                 },
                 diff_from_originating_location_range: 0,
             })
@@ -179,45 +379,422 @@ fn lines_to_reversed_map(lines: &[&str], fill_gaps: bool) -> Vec<RangeMap> {
         .map(RangeMap::from_line)
         .collect::<Vec<_>>();
     // sort range maps by starting point ascending
-    range_maps.sort_by(|a, b| a.source_start.cmp(&b.source_start));
+    range_maps.sort_by_key(|range_map| range_map.source_start);
     // fill in any gaps between ranges with a 'zero-diff' range, that just maps any input
     // number directly to the same number as output
     let mut range_maps_with_intermediate_ranges: Vec<RangeMap> = Vec::new();
-    for (i, pair) in range_maps.windows(2).enumerate() {
-        let first = pair[0];
-        let second = pair[1];
-        if fill_gaps && (i == 0 && first.source_start > 0) {
-            range_maps_with_intermediate_ranges.push(RangeMap {
-                source_start: 0,
-                source_end: first.source_start - 1,
-                dest_source_diff: 0,
-            });
+    // the lowest value we haven't covered yet, or None once we've reached the max possible int;
+    // stepping through the ranges one at a time (rather than in pairs) means a map with just one
+    // line still gets its gaps filled
+    let mut next_uncovered = Some(0);
+    for range_map in range_maps {
+        if let Some(uncovered) = next_uncovered {
+            // the ranges are inclusive on both start and end
+            if fill_gaps && range_map.source_start > uncovered {
+                range_maps_with_intermediate_ranges.push(RangeMap {
+                    source_start: uncovered,
+                    source_end: range_map.source_start - 1,
+                    dest_source_diff: 0,
+                });
+            }
         }
-        range_maps_with_intermediate_ranges.push(first);
-        let first_end = first.source_end;
-        let second_start = second.source_start;
-        // the ranges are inclusive on both start and end
-        if fill_gaps && (second_start - first_end > 1) {
+        range_maps_with_intermediate_ranges.push(range_map);
+        next_uncovered = range_map.source_end.checked_add(1);
+    }
+    if let Some(uncovered) = next_uncovered {
+        if fill_gaps {
             range_maps_with_intermediate_ranges.push(RangeMap {
-                source_start: first_end + 1,
-                source_end: second_start - 1,
+                source_start: uncovered,
+                source_end: i64::MAX,
                 dest_source_diff: 0,
             });
         }
-        if i + 2 >= range_maps.len() {
-            // we've reached the last pair of values -- we're checking i+2 instead
-            // of i+1 because windows() stops at the last full window
-            range_maps_with_intermediate_ranges.push(second);
-            if fill_gaps && (second.source_end < i64::MAX) {
-                range_maps_with_intermediate_ranges.push(RangeMap {
-                    source_start: second.source_end + 1,
-                    source_end: i64::MAX,
-                    dest_source_diff: 0,
+    }
+    range_maps_with_intermediate_ranges
+}
+
+#[derive(Debug, PartialEq)]
+enum AlmanacIssueKind {
+    // the seeds line has something other than integers on it
+    MalformedSeeds,
+    // part 2 reads the seeds in (start, length) pairs
+    OddSeedCount(usize),
+    // a pair of seeds whose start + length - 1 doesn't fit in an i64
+    SeedOverflow,
+    // a section header that isn't `<source>-to-<destination> map:`
+    MalformedHeader,
+    Malformed,
+    NonPositiveLength(i64),
+    // start + range - 1 (or the diff between source and destination) doesn't fit in an i64
+    Overflow,
+    OverlappingSource { other_line_number: usize },
+    // not an error, but worth knowing about
+    SingleEntry,
+}
+
+#[derive(Debug, PartialEq)]
+struct AlmanacIssue {
+    section: String,
+    // 1-based line number within the whole almanac
+    line_number: usize,
+    kind: AlmanacIssueKind,
+}
+
+impl AlmanacIssue {
+    fn is_error(&self) -> bool {
+        self.kind != AlmanacIssueKind::SingleEntry
+    }
+}
+
+impl fmt::Display for AlmanacIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use AlmanacIssueKind::*;
+        match self.kind {
+            MalformedSeeds | OddSeedCount(_) | SeedOverflow | MalformedHeader => {
+                write!(f, "{}, line {}: ", self.section, self.line_number)?
+            }
+            _ => write!(f, "{} map, line {}: ", self.section, self.line_number)?,
+        }
+        match self.kind {
+            MalformedSeeds => write!(f, "expected integers"),
+            OddSeedCount(count) => {
+                write!(f, "{count} values can't be split into start, length pairs")
+            }
+            SeedOverflow => write!(f, "a seed range does not fit in an i64"),
+            MalformedHeader => write!(f, "expected a header like `seed-to-soil map:`"),
+            Malformed => write!(f, "expected three integers"),
+            NonPositiveLength(len) => write!(f, "range length {len} is not positive"),
+            Overflow => write!(f, "range does not fit in an i64"),
+            OverlappingSource { other_line_number } => write!(
+                f,
+                "source range overlaps the one on line {other_line_number}"
+            ),
+            SingleEntry => write!(f, "map has only one entry"),
+        }
+    }
+}
+
+// Checks the raw almanac text for anything `RangeMap::from_line` and the solvers would trip
+// over, before we try to parse it for real.
+fn validate_almanac(file_content: &str) -> Vec<AlmanacIssue> {
+    struct Section {
+        name: String,
+        header_line_number: usize,
+        // (line number, source start, source end) for each well-formed line
+        entries: Vec<(usize, i64, i64)>,
+    }
+
+    let mut issues: Vec<AlmanacIssue> = Vec::new();
+    let mut seeds_issue = |kind| {
+        issues.push(AlmanacIssue {
+            section: "seeds".to_string(),
+            line_number: 1,
+            kind,
+        })
+    };
+    // read the same way `Almanac::parse` does
+    let seeds_paragraph = file_content.split("\n\n").next().unwrap_or("");
+    let seeds = seeds_paragraph
+        .split_once(':')
+        .map_or("", |(_, seeds)| seeds)
+        .split_whitespace()
+        .map(|s| s.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>();
+    match seeds {
+        Err(_) => seeds_issue(AlmanacIssueKind::MalformedSeeds),
+        Ok(seeds) if seeds.len() % 2 == 1 => {
+            seeds_issue(AlmanacIssueKind::OddSeedCount(seeds.len()))
+        }
+        Ok(seeds) => {
+            if seeds.chunks(2).any(|seed_pair| {
+                seed_pair[1]
+                    .checked_sub(1)
+                    .and_then(|range| seed_pair[0].checked_add(range))
+                    .is_none()
+            }) {
+                seeds_issue(AlmanacIssueKind::SeedOverflow);
+            }
+        }
+    }
+
+    let mut sections: Vec<Section> = Vec::new();
+    // the seeds take up the first paragraph, and every paragraph after that starts with a header
+    let mut in_seeds = true;
+    let mut at_header = false;
+    for (i, line) in file_content.lines().enumerate() {
+        let line_number = i + 1;
+        if line.is_empty() {
+            in_seeds = false;
+            at_header = true;
+            continue;
+        }
+        if in_seeds {
+            continue;
+        }
+        if at_header {
+            at_header = false;
+            let is_well_formed = line
+                .strip_suffix(" map:")
+                .is_some_and(|categories| categories.contains("-to-"));
+            if !is_well_formed {
+                issues.push(AlmanacIssue {
+                    section: line.to_string(),
+                    line_number,
+                    kind: AlmanacIssueKind::MalformedHeader,
                 });
             }
+            sections.push(Section {
+                name: line.strip_suffix(" map:").unwrap_or(line).to_string(),
+                header_line_number: line_number,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+        let section = sections
+            .last_mut()
+            .expect("Every paragraph after the seeds starts with a header");
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut issue = |kind| {
+            issues.push(AlmanacIssue {
+                section: section.name.clone(),
+                line_number,
+                kind,
+            })
+        };
+        let nums = line
+            .split_whitespace()
+            .map(|s| s.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>();
+        let (dest_start, source_start, range) = match nums.as_deref() {
+            Ok(&[dest_start, source_start, range]) => (dest_start, source_start, range),
+            _ => {
+                issue(AlmanacIssueKind::Malformed);
+                continue;
+            }
+        };
+        if range <= 0 {
+            issue(AlmanacIssueKind::NonPositiveLength(range));
+            continue;
         }
+        let (Some(source_end), Some(_), Some(_)) = (
+            source_start.checked_add(range - 1),
+            dest_start.checked_add(range - 1),
+            dest_start.checked_sub(source_start),
+        ) else {
+            issue(AlmanacIssueKind::Overflow);
+            continue;
+        };
+        section
+            .entries
+            .push((line_number, source_start, source_end));
+    }
+
+    for Section {
+        name,
+        header_line_number,
+        mut entries,
+    } in sections
+    {
+        if entries.len() == 1 {
+            issues.push(AlmanacIssue {
+                section: name.clone(),
+                line_number: header_line_number,
+                kind: AlmanacIssueKind::SingleEntry,
+            });
+        }
+        entries.sort_by_key(|&(_, source_start, _)| source_start);
+        // the line whose source range reaches furthest so far, and where it ends
+        let mut furthest: Option<(usize, i64)> = None;
+        for (line_number, source_start, source_end) in entries {
+            match furthest {
+                Some((other_line_number, furthest_end)) if source_start <= furthest_end => {
+                    issues.push(AlmanacIssue {
+                        section: name.clone(),
+                        line_number,
+                        kind: AlmanacIssueKind::OverlappingSource { other_line_number },
+                    });
+                    if source_end > furthest_end {
+                        furthest = Some((line_number, source_end));
+                    }
+                }
+                _ => furthest = Some((line_number, source_end)),
+            }
+        }
+    }
+    issues.sort_by_key(|issue| issue.line_number);
+    issues
+}
+
+#[derive(Debug)]
+struct CategoryMap {
+    source: String,
+    destination: String,
+    // sorted by source start, with no gaps filled
+    range_maps: Vec<RangeMap>,
+    // sorted by destination start, and again with no gaps filled
+    unfilled_reversed_range_maps: Vec<RangeMap>,
+    reversed_range_maps: Vec<RangeMap>,
+}
+
+impl CategoryMap {
+    fn from_section(section: &str) -> CategoryMap {
+        let lines = section
+            .split("\n")
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        // headers look like 'seed-to-soil map:'
+        let (source, destination) = lines[0]
+            .strip_suffix(" map:")
+            .and_then(|categories| categories.split_once("-to-"))
+            .unwrap_or_else(|| panic!("Could not parse map header {}", lines[0]));
+        let unfilled_reversed_range_maps = lines_to_reversed_map(&lines[1..], false);
+        let mut range_maps = unfilled_reversed_range_maps
+            .iter()
+            .map(RangeMap::reversed)
+            .collect::<Vec<_>>();
+        range_maps.sort_by_key(|range_map| range_map.source_start);
+        CategoryMap {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            range_maps,
+            unfilled_reversed_range_maps,
+            reversed_range_maps: lines_to_reversed_map(&lines[1..], true),
+        }
+    }
+
+    // Every source value that this map sends into one of the given destination intervals. A
+    // destination value can come from a range map, and also from itself if no range map claims
+    // it as a source, so we check both rather than relying on the gap-filled reversed maps.
+    fn preimage(&self, intervals: &[Interval]) -> Vec<Interval> {
+        let mut preimage: Vec<Interval> = Vec::new();
+        for &interval in intervals {
+            for (piece, diff) in split_interval(&self.unfilled_reversed_range_maps, interval) {
+                if let Some(diff) = diff {
+                    preimage.push(Interval {
+                        start: piece.start + diff,
+                        end: piece.end + diff,
+                    });
+                }
+            }
+            for (piece, diff) in split_interval(&self.range_maps, interval) {
+                if diff.is_none() {
+                    preimage.push(piece);
+                }
+            }
+        }
+        merge_intervals(preimage)
+    }
+}
+
+// The almanac's maps form a directed graph, with categories as nodes and each map as an edge
+// from its source category to its destination category.
+#[derive(Debug)]
+struct Almanac {
+    seeds: Vec<i64>,
+    category_maps: Vec<CategoryMap>,
+}
+
+impl Almanac {
+    fn parse(file_content: &str) -> Almanac {
+        let split = file_content.split("\n\n").collect::<Vec<_>>();
+        let seeds_str = split[0].split_once(':').map_or("", |(_, seeds)| seeds);
+        let seeds = seeds_str
+            .split_whitespace()
+            .map(|s| s.parse::<i64>().unwrap())
+            .collect::<Vec<_>>();
+        let category_maps = split[1..]
+            .iter()
+            .filter(|section| !section.trim().is_empty())
+            .map(|section| CategoryMap::from_section(section))
+            .collect::<Vec<_>>();
+        Almanac {
+            seeds,
+            category_maps,
+        }
+    }
+
+    // Breadth-first search for the shortest chain of maps that leads from the source category
+    // to the target category.
+    fn find_path(&self, source: &str, target: &str) -> Option<Vec<&CategoryMap>> {
+        // category -> index of the map we used to first reach it
+        let mut reached_by: HashMap<&str, Option<usize>> = HashMap::from([(source, None)]);
+        let mut queue: VecDeque<&str> = VecDeque::from([source]);
+        while let Some(category) = queue.pop_front() {
+            if category == target {
+                let mut path: Vec<&CategoryMap> = Vec::new();
+                let mut curr_category = category;
+                while let Some(map_index) = reached_by[curr_category] {
+                    let category_map = &self.category_maps[map_index];
+                    path.push(category_map);
+                    curr_category = &category_map.source;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (i, category_map) in self.category_maps.iter().enumerate() {
+                if category_map.source == category
+                    && !reached_by.contains_key(category_map.destination.as_str())
+                {
+                    reached_by.insert(&category_map.destination, Some(i));
+                    queue.push_back(&category_map.destination);
+                }
+            }
+        }
+        None
+    }
+
+    // Builds a reversed almanac that searches back from the target category to the given
+    // ranges of source values.
+    fn reversed_almanac(
+        &self,
+        source: &str,
+        target: &str,
+        source_to_finished: Vec<RangeMap>,
+    ) -> Option<ReversedAlmanac> {
+        let mut path = self.find_path(source, target)?;
+        path.reverse();
+        Some(ReversedAlmanac::new(&path, source_to_finished))
+    }
+
+    // The forward alternative to searching a reversed almanac: push the source intervals
+    // through every stage on the way to the target category, splitting them as we go. Returns
+    // every target value reachable from the source intervals, as sorted, merged intervals.
+    fn propagate_forward(
+        &self,
+        source: &str,
+        target: &str,
+        source_intervals: &[Interval],
+    ) -> Option<Vec<Interval>> {
+        let path = self.find_path(source, target)?;
+        let mut intervals = merge_intervals(source_intervals.to_vec());
+        for category_map in path {
+            intervals = propagate_intervals(&category_map.range_maps, &intervals);
+        }
+        Some(intervals)
+    }
+
+    // The inverse of `propagate_forward`: every source value that ends up somewhere in the
+    // given intervals of the target category.
+    fn trace_back(
+        &self,
+        source: &str,
+        target: &str,
+        target_intervals: &[Interval],
+    ) -> Option<Vec<Interval>> {
+        let path = self.find_path(source, target)?;
+        let mut intervals = merge_intervals(target_intervals.to_vec());
+        for category_map in path.iter().rev() {
+            intervals = category_map.preimage(&intervals);
+        }
+        Some(intervals)
+    }
+
+    fn compose(&self, source: &str, target: &str) -> Option<ComposedMap> {
+        let path = self.find_path(source, target)?;
+        Some(ComposedMap::compose(source, target, &path))
     }
-    range_maps_with_intermediate_ranges
 }
 
 fn part_2_seed_to_finished(seeds: &[i64]) -> Vec<RangeMap> {
@@ -246,67 +823,526 @@ fn part_1_seed_to_finished(seeds: &[i64]) -> Vec<RangeMap> {
         .collect::<Vec<_>>()
 }
 
+fn range_maps_to_intervals(range_maps: &[RangeMap]) -> Vec<Interval> {
+    range_maps
+        .iter()
+        .map(|range_map| Interval {
+            start: range_map.source_start,
+            end: range_map.source_end,
+        })
+        .collect::<Vec<_>>()
+}
+
 fn main() {
+    // by default we go from seeds to locations, but any two categories can be given instead;
+    // the values on the almanac's first line are then read as values of the source category
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    // `--export <path>` writes the composed source-to-target function out as JSON if the path
+    // ends in .json, or as CSV otherwise
+    let export_path = match args.iter().position(|arg| arg == "--export") {
+        Some(i) if i + 1 < args.len() => args.drain(i..i + 2).nth(1),
+        Some(_) => {
+            println!("Error: usage is `--export <path>`");
+            return;
+        }
+        None => None,
+    };
+    // `--trace <category> <start> <end>` lists the source values that end up anywhere in the
+    // inclusive range start..=end of the given category, instead of solving
+    let trace = match args.iter().position(|arg| arg == "--trace") {
+        Some(i) if i + 3 < args.len() => {
+            let trace = args.drain(i..i + 4).skip(1).collect::<Vec<_>>();
+            match (trace[1].parse::<i64>(), trace[2].parse::<i64>()) {
+                (Ok(start), Ok(end)) => Some((trace[0].clone(), Interval { start, end })),
+                _ => {
+                    println!("Error: usage is `--trace <category> <start> <end>`");
+                    return;
+                }
+            }
+        }
+        Some(_) => {
+            println!("Error: usage is `--trace <category> <start> <end>`");
+            return;
+        }
+        None => None,
+    };
+    let (source, target) = if args.len() > 1 {
+        (args[0].as_str(), args[1].as_str())
+    } else {
+        ("seed", "location")
+    };
+
     let file_content = fs::read_to_string("resources/input_1").unwrap();
-    let split = file_content.split("\n\n").collect::<Vec<_>>();
-    let seeds_str = split[0].split(": ").collect::<Vec<_>>()[1];
-    let seeds = seeds_str
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .iter()
-        .map(|s| s.parse::<i64>().unwrap())
-        .collect::<Vec<_>>();
+    let issues = validate_almanac(&file_content);
+    for issue in issues.iter() {
+        let level = if issue.is_error() { "Error" } else { "Warning" };
+        println!("{level}: {issue}");
+    }
+    if issues.iter().any(AlmanacIssue::is_error) {
+        return;
+    }
+    let almanac = Almanac::parse(&file_content);
 
-    let soil_to_seed = lines_to_reversed_map(&split[1].split("\n").collect::<Vec<_>>()[1..], true);
-    let fertilizer_to_soil =
-        lines_to_reversed_map(&split[2].split("\n").collect::<Vec<_>>()[1..], true);
-    let water_to_fertilizer =
-        lines_to_reversed_map(&split[3].split("\n").collect::<Vec<_>>()[1..], true);
-    let light_to_water =
-        lines_to_reversed_map(&split[4].split("\n").collect::<Vec<_>>()[1..], true);
-    let temperature_to_light =
-        lines_to_reversed_map(&split[5].split("\n").collect::<Vec<_>>()[1..], true);
-    let humidity_to_temperature =
-        lines_to_reversed_map(&split[6].split("\n").collect::<Vec<_>>()[1..], true);
-    let location_to_humidity = lines_to_reversed_map(
-        &split[7]
-            .split("\n")
-            .filter(|s| s != &"")
-            .collect::<Vec<_>>()[1..],
-        true,
-    );
+    if let Some((category, traced_interval)) = trace {
+        let category = category.as_str();
+        let Some(source_intervals) = almanac.trace_back(source, category, &[traced_interval])
+        else {
+            println!("No chain of maps leads from {source} to {category}");
+            return;
+        };
+        let seed_intervals = merge_intervals(range_maps_to_intervals(&part_2_seed_to_finished(
+            &almanac.seeds,
+        )));
+        let listed_intervals = intersect_intervals(&source_intervals, &seed_intervals);
+        println!(
+            "{category} values {}..={} come from these {source} values:",
+            traced_interval.start, traced_interval.end
+        );
+        for interval in source_intervals {
+            println!("  {}..={}", interval.start, interval.end);
+        }
+        println!("Of those, these {source} values are listed in part 2:");
+        for interval in listed_intervals {
+            println!("  {}..={}", interval.start, interval.end);
+        }
+        return;
+    }
 
-    let part_1_seed_to_finished = part_1_seed_to_finished(&seeds);
-    let part_2_seed_to_finished = part_2_seed_to_finished(&seeds);
-
-    let part_1_reversed_almanac: ReversedAlmanac = ReversedAlmanac {
-        location_to_humidity: location_to_humidity.clone(),
-        humidity_to_temperature: humidity_to_temperature.clone(),
-        temperature_to_light: temperature_to_light.clone(),
-        light_to_water: light_to_water.clone(),
-        water_to_fertilizer: water_to_fertilizer.clone(),
-        fertilizer_to_soil: fertilizer_to_soil.clone(),
-        soil_to_seed: soil_to_seed.clone(),
-        seed_to_finished: part_1_seed_to_finished,
+    let (Some(part_1_reversed_almanac), Some(part_2_reversed_almanac)) = (
+        almanac.reversed_almanac(source, target, part_1_seed_to_finished(&almanac.seeds)),
+        almanac.reversed_almanac(source, target, part_2_seed_to_finished(&almanac.seeds)),
+    ) else {
+        println!("No chain of maps leads from {source} to {target}");
+        return;
     };
-    let part_2_reversed_almanac: ReversedAlmanac = ReversedAlmanac {
-        location_to_humidity,
-        humidity_to_temperature,
-        temperature_to_light,
-        light_to_water,
-        water_to_fertilizer,
-        fertilizer_to_soil,
-        soil_to_seed,
-        seed_to_finished: part_2_seed_to_finished,
+
+    // with no seeds listed there's nothing to reach
+    let (Some(part_1_solution), Some(part_2_solution)) = (
+        part_1_reversed_almanac.depth_first_search_by_range(),
+        part_2_reversed_almanac.depth_first_search_by_range(),
+    ) else {
+        println!("No {target} values are reachable from the listed {source} values");
+        return;
     };
+    println!("Part 1 solution: {part_1_solution}");
+    println!("Part 2 solution: {part_2_solution}");
 
-    let part_1_solution = part_1_reversed_almanac
-        .depth_first_search_by_range()
+    let seed_intervals = range_maps_to_intervals(&part_2_seed_to_finished(&almanac.seeds));
+    let target_intervals = almanac
+        .propagate_forward(source, target, &seed_intervals)
         .unwrap();
-    println!("Part 1 solution: {part_1_solution}");
+    match target_intervals.first() {
+        Some(lowest_interval) => println!(
+            "Part 2 solution via forward propagation: {} ({} {target} intervals reachable)",
+            lowest_interval.start,
+            target_intervals.len()
+        ),
+        None => println!("Forward propagation reached no {target} values"),
+    }
 
-    let part_2_solution = part_2_reversed_almanac
-        .depth_first_search_by_range()
+    let composed_map = almanac.compose(source, target).unwrap();
+    let part_1_solution = almanac
+        .seeds
+        .iter()
+        .map(|&seed| composed_map.map_value(seed))
+        .min()
         .unwrap();
-    println!("Part 2 solution: {part_2_solution}");
+    println!(
+        "Part 1 solution via composed map: {part_1_solution} ({} segments)",
+        composed_map.segments.len()
+    );
+    if let Some(export_path) = export_path {
+        let exported = if export_path.ends_with(".json") {
+            composed_map.to_json()
+        } else {
+            composed_map.to_csv()
+        };
+        fs::write(&export_path, exported).unwrap();
+        println!("Wrote composed {source}-to-{target} map to {export_path}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lowest_reachable(almanac: &Almanac, source: &str, target: &str) -> Option<i64> {
+        almanac
+            .reversed_almanac(source, target, part_2_seed_to_finished(&almanac.seeds))?
+            .depth_first_search_by_range()
+    }
+
+    #[test]
+    fn test_sample() {
+        let almanac = Almanac::parse(&fs::read_to_string("resources/sample_1").unwrap());
+        let part_1_solution = almanac
+            .reversed_almanac("seed", "location", part_1_seed_to_finished(&almanac.seeds))
+            .unwrap()
+            .depth_first_search_by_range();
+        assert_eq!(part_1_solution, Some(35));
+        assert_eq!(lowest_reachable(&almanac, "seed", "location"), Some(46));
+    }
+
+    #[test]
+    fn test_custom_categories() {
+        let almanac = Almanac::parse(
+            "seeds: 1 2\n\n\
+             bean-to-sprout map:\n\
+             3 0 3\n\
+             0 3 3\n\n\
+             sprout-to-stalk map:\n\
+             10 0 2\n\
+             0 2 10\n\n\
+             bean-to-pod map:\n\
+             5 0 5\n\
+             0 5 5\n",
+        );
+        // beans 1 and 2 become sprouts 4 and 5, which become stalks 2 and 3
+        assert_eq!(lowest_reachable(&almanac, "bean", "stalk"), Some(2));
+        assert_eq!(lowest_reachable(&almanac, "bean", "sprout"), Some(4));
+        assert_eq!(lowest_reachable(&almanac, "bean", "pod"), Some(6));
+        assert_eq!(lowest_reachable(&almanac, "bean", "bean"), Some(1));
+        assert_eq!(lowest_reachable(&almanac, "stalk", "bean"), None);
+    }
+
+    // xorshift, so we don't need a crate just to generate test almanacs
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, bound: i64) -> i64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as i64
+        }
+    }
+
+    // The reversed search assumes that each map just shuffles the values it covers around
+    // (as puzzle inputs do), so every stage here cuts 0..domain into blocks and permutes them.
+    // Blocks that land back where they started are usually left out, to leave gaps.
+    fn random_almanac_text(rng: &mut Rng, num_stages: usize, domain: i64) -> String {
+        let mut seeds: Vec<String> = Vec::new();
+        for _ in 0..1 + rng.below(4) {
+            seeds.push(rng.below(domain + 20).to_string());
+            seeds.push((1 + rng.below(domain / 4)).to_string());
+        }
+        let mut text = format!("seeds: {}\n", seeds.join(" "));
+        for stage in 0..num_stages {
+            let mut block_starts = vec![0];
+            for _ in 0..1 + rng.below(8) {
+                block_starts.push(1 + rng.below(domain - 1));
+            }
+            block_starts.sort();
+            block_starts.dedup();
+            block_starts.push(domain);
+            let mut blocks = block_starts
+                .windows(2)
+                .map(|pair| (pair[0], pair[1] - pair[0]))
+                .collect::<Vec<_>>();
+            for i in (1..blocks.len()).rev() {
+                blocks.swap(i, rng.below(i as i64 + 1) as usize);
+            }
+            let mut lines: Vec<String> = Vec::new();
+            let mut dest_start = 0;
+            for (source_start, len) in blocks {
+                if source_start != dest_start || rng.below(3) == 0 {
+                    lines.push(format!("{dest_start} {source_start} {len}"));
+                }
+                dest_start += len;
+            }
+            text.push_str(&format!("\nc{stage}-to-c{} map:\n", stage + 1));
+            text.push_str(&lines.join("\n"));
+            text.push('\n');
+        }
+        text
+    }
+
+    fn map_value(path: &[&CategoryMap], value: i64) -> i64 {
+        path.iter().fold(value, |value, category_map| {
+            category_map
+                .range_maps
+                .iter()
+                .find(|range_map| range_map.source_start <= value && value <= range_map.source_end)
+                .map_or(value, |range_map| value + range_map.dest_source_diff)
+        })
+    }
+
+    #[test]
+    fn test_forward_propagation_sample() {
+        let almanac = Almanac::parse(&fs::read_to_string("resources/sample_1").unwrap());
+        let seed_intervals = range_maps_to_intervals(&part_2_seed_to_finished(&almanac.seeds));
+        let location_intervals = almanac
+            .propagate_forward("seed", "location", &seed_intervals)
+            .unwrap();
+        assert_eq!(location_intervals[0].start, 46);
+        let num_locations = location_intervals
+            .iter()
+            .map(|interval| interval.end - interval.start + 1)
+            .sum::<i64>();
+        assert_eq!(num_locations, 14 + 13);
+
+        // with no seeds listed nothing is reachable, which shouldn't trip anything up
+        let sample = fs::read_to_string("resources/sample_1").unwrap();
+        let (_, maps) = sample.split_once("\n\n").unwrap();
+        let almanac = Almanac::parse(&format!("seeds:\n\n{maps}"));
+        assert!(almanac.seeds.is_empty());
+        let location_intervals = almanac.propagate_forward("seed", "location", &[]).unwrap();
+        assert!(location_intervals.is_empty());
+        assert_eq!(lowest_reachable(&almanac, "seed", "location"), None);
+    }
+
+    #[test]
+    fn test_forward_propagation_matches_reversed_search() {
+        let mut rng = Rng(0x2023_0005);
+        for _ in 0..300 {
+            let num_stages = 1 + rng.below(6) as usize;
+            let domain = 20 + rng.below(200);
+            let almanac = Almanac::parse(&random_almanac_text(&mut rng, num_stages, domain));
+            let target = format!("c{num_stages}");
+            let path = almanac.find_path("c0", &target).unwrap();
+
+            for seed_to_finished in [
+                part_1_seed_to_finished(&almanac.seeds),
+                part_2_seed_to_finished(&almanac.seeds),
+            ] {
+                let seed_intervals = range_maps_to_intervals(&seed_to_finished);
+                let target_intervals = almanac
+                    .propagate_forward("c0", &target, &seed_intervals)
+                    .unwrap();
+                let reversed_lowest = almanac
+                    .reversed_almanac("c0", &target, seed_to_finished)
+                    .unwrap()
+                    .depth_first_search_by_range();
+                assert_eq!(reversed_lowest, Some(target_intervals[0].start));
+
+                // and the intervals should hold exactly the values we get one seed at a time
+                let mut expected = seed_intervals
+                    .iter()
+                    .flat_map(|interval| interval.start..=interval.end)
+                    .map(|seed| map_value(&path, seed))
+                    .collect::<Vec<_>>();
+                expected.sort();
+                expected.dedup();
+                let actual = target_intervals
+                    .iter()
+                    .flat_map(|interval| interval.start..=interval.end)
+                    .collect::<Vec<_>>();
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_composed_map() {
+        let mut rng = Rng(0x2023_0030);
+        for _ in 0..100 {
+            let num_stages = 1 + rng.below(6) as usize;
+            let domain = 20 + rng.below(200);
+            let almanac = Almanac::parse(&random_almanac_text(&mut rng, num_stages, domain));
+            let target = format!("c{num_stages}");
+            let path = almanac.find_path("c0", &target).unwrap();
+            let composed_map = almanac.compose("c0", &target).unwrap();
+
+            let segments = &composed_map.segments;
+            assert_eq!(segments[0].source_start, i64::MIN);
+            assert_eq!(segments[segments.len() - 1].source_end, i64::MAX);
+            for pair in segments.windows(2) {
+                assert_eq!(pair[0].source_end + 1, pair[1].source_start);
+                assert_ne!(pair[0].dest_source_diff, pair[1].dest_source_diff);
+            }
+            for value in [i64::MIN, -1, i64::MAX].into_iter().chain(0..domain + 10) {
+                assert_eq!(composed_map.map_value(value), map_value(&path, value));
+            }
+        }
+    }
+
+    #[test]
+    fn test_composed_map_export() {
+        let almanac = Almanac::parse(&fs::read_to_string("resources/sample_1").unwrap());
+        let composed_map = almanac.compose("seed", "soil").unwrap();
+        assert_eq!(
+            composed_map.to_csv(),
+            "seed_start,seed_end,offset\n\
+             -9223372036854775808,49,0\n\
+             50,97,2\n\
+             98,99,-48\n\
+             100,9223372036854775807,0\n"
+        );
+        assert!(composed_map.to_json().contains(
+            "\"segments\": [\n    {\"start\": -9223372036854775808, \"end\": 49, \"offset\": 0},"
+        ));
+
+        // category names can hold anything but "-to-", so they need escaping
+        let almanac = Almanac::parse("seeds: 1\n\nthe \"seed\"-to-C:\\soil map:\n5 0 3\n");
+        let composed_map = almanac.compose("the \"seed\"", "C:\\soil").unwrap();
+        let json = composed_map.to_json();
+        assert!(json.contains("  \"source\": \"the \\\"seed\\\"\",\n"));
+        assert!(json.contains("  \"destination\": \"C:\\\\soil\",\n"));
+    }
+
+    #[test]
+    fn test_trace_back_sample() {
+        let almanac = Almanac::parse(&fs::read_to_string("resources/sample_1").unwrap());
+        let location_46 = Interval { start: 46, end: 46 };
+        let seeds = almanac.trace_back("seed", "location", &[location_46]);
+        assert_eq!(seeds, Some(vec![Interval { start: 82, end: 82 }]));
+        // no seed-to-soil entry covers seed 14, so it stays 14
+        let soil_14 = Interval { start: 14, end: 14 };
+        let seeds = almanac.trace_back("seed", "soil", &[soil_14]);
+        assert_eq!(seeds, Some(vec![Interval { start: 14, end: 14 }]));
+        // soil 52 comes from seed 50 (mapped) and nowhere else, since seed 52 is mapped too
+        let soil_52 = Interval { start: 52, end: 52 };
+        let seeds = almanac.trace_back("seed", "soil", &[soil_52]);
+        assert_eq!(seeds, Some(vec![Interval { start: 50, end: 50 }]));
+
+        // here b 10 and 11 are reached both through the first entry and by a 10 and 11
+        // passing through unmapped
+        let almanac = Almanac::parse("seeds: 0 1\n\na-to-b map:\n10 0 3\n50 60 2\n");
+        let b_10_to_11 = Interval { start: 10, end: 11 };
+        assert_eq!(
+            almanac.trace_back("a", "b", &[b_10_to_11]),
+            Some(vec![
+                Interval { start: 0, end: 1 },
+                Interval { start: 10, end: 11 }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_trace_back_matches_brute_force() {
+        let mut rng = Rng(0x2023_0031);
+        for _ in 0..100 {
+            let num_stages = 1 + rng.below(6) as usize;
+            let domain = 20 + rng.below(200);
+            let almanac = Almanac::parse(&random_almanac_text(&mut rng, num_stages, domain));
+            let category = format!("c{}", 1 + rng.below(num_stages as i64));
+            let path = almanac.find_path("c0", &category).unwrap();
+            let start = rng.below(domain + 10) - 5;
+            let traced_interval = Interval {
+                start,
+                end: start + rng.below(domain / 2),
+            };
+
+            let source_intervals = almanac
+                .trace_back("c0", &category, &[traced_interval])
+                .unwrap();
+            let actual = source_intervals
+                .iter()
+                .flat_map(|interval| interval.start..=interval.end)
+                .collect::<Vec<_>>();
+            // everything outside 0..domain maps to itself, so this covers every candidate
+            let expected = (traced_interval.start.min(0)..=traced_interval.end.max(domain))
+                .filter(|&value| {
+                    let mapped = map_value(&path, value);
+                    traced_interval.start <= mapped && mapped <= traced_interval.end
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_validate_almanac() {
+        let file_content = "seeds: 1 2\n\n\
+                            a-to-b map:\n\
+                            0 10 5\n\
+                            20 12 5\n\
+                            40 20 0\n\
+                            9223372036854775800 30 10\n\
+                            50 60\n\n\
+                            b-to-c map:\n\
+                            0 10 5\n";
+        let issues = validate_almanac(file_content);
+        let issue_kinds = issues
+            .iter()
+            .map(|issue| (issue.section.as_str(), issue.line_number, &issue.kind))
+            .collect::<Vec<_>>();
+        use AlmanacIssueKind::*;
+        assert_eq!(
+            issue_kinds,
+            vec![
+                (
+                    "a-to-b",
+                    5,
+                    &OverlappingSource {
+                        other_line_number: 4
+                    }
+                ),
+                ("a-to-b", 6, &NonPositiveLength(0)),
+                ("a-to-b", 7, &Overflow),
+                ("a-to-b", 8, &Malformed),
+                ("b-to-c", 10, &SingleEntry),
+            ]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "a-to-b map, line 5: source range overlaps the one on line 4"
+        );
+        assert!(validate_almanac(&fs::read_to_string("resources/input_1").unwrap()).is_empty());
+
+        let kinds = |file_content: &str| {
+            validate_almanac(file_content)
+                .into_iter()
+                .map(|issue| (issue.line_number, issue.kind))
+                .collect::<Vec<_>>()
+        };
+        let maps = "\n\na-to-b map:\n0 10 5\n20 30 5\n";
+        assert_eq!(kinds(&format!("seeds: 1 2 x{maps}")), [(1, MalformedSeeds)]);
+        assert_eq!(
+            kinds(&format!("seeds: 1 2 3{maps}")),
+            [(1, OddSeedCount(3))]
+        );
+        assert_eq!(
+            kinds(&format!("seeds: 1 2 9223372036854775800 10{maps}")),
+            [(1, SeedOverflow)]
+        );
+        let issues = validate_almanac("seeds: 1 2\n\na to b:\n0 10 5\n20 30 5\n");
+        assert_eq!(
+            issues
+                .iter()
+                .map(|issue| (issue.line_number, &issue.kind))
+                .collect::<Vec<_>>(),
+            [(3, &MalformedHeader)]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "a to b:, line 3: expected a header like `seed-to-soil map:`"
+        );
+        // a header without the blank line before it reads as a bad line of the previous map,
+        // and so do the lines after it
+        assert_eq!(
+            kinds("seeds: 1 2\n\na-to-b map:\n0 10 5\n20 30 5\nb-to-c map:\n0 10 5\n"),
+            [
+                (6, Malformed),
+                (
+                    7,
+                    OverlappingSource {
+                        other_line_number: 4
+                    }
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_entry_map() {
+        let almanac = Almanac::parse("seeds: 5 10\n\na-to-b map:\n100 8 4\n");
+        // a 5..=7 pass through unmapped, 8..=11 move up to 100..=103, and 12..=14 pass through
+        let expected = vec![
+            Interval { start: 5, end: 7 },
+            Interval { start: 12, end: 14 },
+            Interval {
+                start: 100,
+                end: 103,
+            },
+        ];
+        let seed_intervals = range_maps_to_intervals(&part_2_seed_to_finished(&almanac.seeds));
+        let b_intervals = almanac.propagate_forward("a", "b", &seed_intervals);
+        assert_eq!(b_intervals, Some(expected));
+        let lowest = almanac
+            .reversed_almanac("a", "b", part_2_seed_to_finished(&almanac.seeds))
+            .unwrap()
+            .depth_first_search_by_range();
+        assert_eq!(lowest, Some(5));
+    }
 }
@@ -0,0 +1,285 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Shl, Shr, Sub};
+use std::str::FromStr;
+
+// Just enough of an arbitrary-precision unsigned integer to solve races with hundreds of digits.
+// Limbs are base 2^32 and stored least significant first, with no trailing zero limbs (so zero
+// has no limbs at all).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn from_limbs(mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    fn num_bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * 32 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {
+            return None;
+        }
+        let mut limbs = self.limbs.clone();
+        let mut borrow = 0_i64;
+        for (i, limb) in limbs.iter_mut().enumerate() {
+            let mut diff = *limb as i64 - borrow - *other.limbs.get(i).unwrap_or(&0) as i64;
+            borrow = 0;
+            if diff < 0 {
+                diff += 1 << 32;
+                borrow = 1;
+            }
+            *limb = diff as u32;
+        }
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    // Divides in place by a small divisor, returning the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0_u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let current = (remainder << 32) | *limb as u64;
+            *limb = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        *self = BigUint::from_limbs(std::mem::take(&mut self.limbs));
+        remainder as u32
+    }
+
+    // The largest x such that x * x <= self, worked out one bit at a time so that we only ever
+    // need shifts, additions and subtractions.
+    pub fn isqrt(&self) -> BigUint {
+        let mut remaining = self.clone();
+        let mut root = BigUint::zero();
+        // the highest power of four that's no bigger than self
+        let mut bit = match self.num_bits() {
+            0 => return root,
+            num_bits => &BigUint::from(1) << ((num_bits - 1) & !1),
+        };
+        while !bit.is_zero() {
+            let candidate = &root + &bit;
+            if remaining >= candidate {
+                remaining = &remaining - &candidate;
+                root = &(&root >> 1) + &bit;
+            } else {
+                root = &root >> 1;
+            }
+            bit = &bit >> 2;
+        }
+        root
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        BigUint::from_limbs(vec![n as u32, (n >> 32) as u32])
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let len = std::cmp::max(self.limbs.len(), other.limbs.len());
+        let mut limbs: Vec<u32> = Vec::with_capacity(len + 1);
+        let mut carry = 0_u64;
+        for i in 0..len {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64
+                + *other.limbs.get(i).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        self.checked_sub(other)
+            .expect("BigUint subtraction underflowed")
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0_u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0_u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Shl<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shl(self, bits: usize) -> BigUint {
+        let (limb_shift, bit_shift) = (bits / 32, bits % 32);
+        let mut limbs = vec![0_u32; limb_shift];
+        let mut carry = 0_u32;
+        for &limb in self.limbs.iter() {
+            limbs.push((limb << bit_shift) | carry);
+            carry = if bit_shift == 0 {
+                0
+            } else {
+                limb >> (32 - bit_shift)
+            };
+        }
+        limbs.push(carry);
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Shr<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shr(self, bits: usize) -> BigUint {
+        let (limb_shift, bit_shift) = (bits / 32, bits % 32);
+        let kept = self.limbs.get(limb_shift..).unwrap_or(&[]);
+        let limbs = kept
+            .iter()
+            .enumerate()
+            .map(|(i, &limb)| {
+                let from_above = match kept.get(i + 1) {
+                    Some(&above) if bit_shift > 0 => above << (32 - bit_shift),
+                    _ => 0,
+                };
+                (limb >> bit_shift) | from_above
+            })
+            .collect::<Vec<_>>();
+        BigUint::from_limbs(limbs)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseBigUintError;
+
+impl FromStr for BigUint {
+    type Err = ParseBigUintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseBigUintError);
+        }
+        let ten = BigUint::from(10);
+        let mut n = BigUint::zero();
+        for c in s.chars() {
+            let digit = c.to_digit(10).ok_or(ParseBigUintError)?;
+            n = &(&n * &ten) + &BigUint::from(digit as u64);
+        }
+        Ok(n)
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // peel off nine decimal digits at a time, least significant first
+        let mut remaining = self.clone();
+        let mut chunks: Vec<u32> = Vec::new();
+        while !remaining.is_zero() {
+            chunks.push(remaining.div_rem_small(1_000_000_000));
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        s.parse::<BigUint>().unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_and_arithmetic() {
+        let a = big("123456789012345678901234567890123456789");
+        let b = big("987654321098765432109876543210");
+        assert_eq!(a.to_string(), "123456789012345678901234567890123456789");
+        assert_eq!(big("0").to_string(), "0");
+        assert_eq!(big("000120").to_string(), "120");
+        assert_eq!("12a".parse::<BigUint>(), Err(ParseBigUintError));
+
+        assert_eq!(
+            (&a + &b).to_string(),
+            "123456789999999999999999999999999999999"
+        );
+        assert_eq!(
+            (&a - &b).to_string(),
+            "123456788024691357802469135780246913579"
+        );
+        assert_eq!(b.checked_sub(&a), None);
+        assert_eq!(
+            (&a * &b).to_string(),
+            "121932631137021795226185032733744855963362292333223746380111126352690"
+        );
+        assert_eq!(&(&a << 70) >> 70, a);
+        assert_eq!((&a >> 200), BigUint::zero());
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..2_000_u64 {
+            let root = BigUint::from(n).isqrt();
+            assert_eq!(root, BigUint::from((n as f64).sqrt() as u64));
+        }
+        let root = big("31415926535897932384626433832795028841971693993751");
+        let square = &root * &root;
+        let one = BigUint::from(1);
+        assert_eq!(square.isqrt(), root);
+        assert_eq!((&square - &one).isqrt(), &root - &one);
+        assert_eq!((&square + &root).isqrt(), root);
+    }
+}
@@ -1,3 +1,7 @@
+mod bignum;
+
+use bignum::BigUint;
+use std::env;
 use std::fs;
 use std::iter::zip;
 
@@ -12,11 +16,33 @@ fn main() {
     let part_1_solution = solve_part_1(filename);
     println!("Part 1 solution: {part_1_solution}");
 
+    // `--model <name> <params...>` re-runs part 1 under different boat physics
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--model") {
+        let model = match parse_model(&args[i + 1..]) {
+            Ok(model) => model,
+            Err(err) => {
+                println!("Error: {err}");
+                return;
+            }
+        };
+        let mut product = 1;
+        for race in parse_races(filename) {
+            let solution = solve_race(model.as_ref(), &race);
+            println!(
+                "Race of {} ms: {} winning options, best hold is {} ms",
+                race.time_ms, solution.num_winning_options, solution.optimal_hold_ms
+            );
+            product *= solution.num_winning_options;
+        }
+        println!("Part 1 solution under {} model: {product}", args[i + 1]);
+    }
+
     let part_2_solution = solve_part_2(filename);
     println!("Part 2 solution: {part_2_solution}");
 }
 
-fn solve_part_1(filename: &str) -> usize {
+fn parse_races(filename: &str) -> Vec<Race> {
     let file_content = fs::read_to_string(filename).unwrap();
     let split = file_content.split("\n").collect::<Vec<_>>();
 
@@ -38,31 +64,18 @@ fn solve_part_1(filename: &str) -> usize {
             record_distance_mm: distance,
         })
     }
+    races
+}
 
-    let mut nums_winning_options: Vec<usize> = Vec::new();
-    for race in races.iter() {
-        let mut possible_distances: Vec<usize> = Vec::new();
-        for holdable_ms in 0..race.time_ms {
-            let speed_mm_per_ms = holdable_ms;
-            possible_distances.push(speed_mm_per_ms * (race.time_ms - holdable_ms));
-        }
-        nums_winning_options.push(
-            possible_distances
-                .iter()
-                .filter(|d| **d > race.record_distance_mm)
-                .count(),
-        );
-    }
-
-    nums_winning_options.iter().product::<usize>()
+// The same race as `Race`, but for kerned inputs whose time and record can be hundreds of
+// digits long.
+#[derive(Debug)]
+struct BigRace {
+    time_ms: BigUint,
+    record_distance_mm: BigUint,
 }
 
-// Brute force worked just fine. If it hadn't, there would be a lot of options:
-// - Binary search to find first winning option, then depend on symmetry to know
-//   that if time=n was first winning option, time=(race_time-n) was last?
-// - Some kind of clever math function that can solve the problem without any interation
-//   whatsover?
-fn solve_part_2(filename: &str) -> usize {
+fn parse_kerned_race(filename: &str) -> BigRace {
     let file_content = fs::read_to_string(filename).unwrap();
     let split = file_content.split("\n").collect::<Vec<_>>();
 
@@ -70,22 +83,467 @@ fn solve_part_2(filename: &str) -> usize {
         .split_whitespace()
         .skip(1)
         .collect::<String>()
-        .parse::<usize>()
+        .parse::<BigUint>()
         .unwrap();
     let record_distance_mm = split[1]
         .split_whitespace()
         .skip(1)
         .collect::<String>()
-        .parse::<usize>()
+        .parse::<BigUint>()
         .unwrap();
+    BigRace {
+        time_ms: race_time_ms,
+        record_distance_mm,
+    }
+}
+
+fn solve_part_1(filename: &str) -> usize {
+    let model = LinearCharge { charge_rate: 1 };
+    parse_races(filename)
+        .iter()
+        .map(|race| solve_race(&model, race).num_winning_options)
+        .product::<usize>()
+}
+
+// Brute force worked just fine at first, but the race is now solved without any iteration,
+// and without caring how many digits the kerned numbers run to.
+fn solve_part_2(filename: &str) -> BigUint {
+    count_winning_options_big(&parse_kerned_race(filename))
+}
+
+// The largest x such that x * x <= n, via Newton's method on integers only, so there's no
+// floating-point rounding to worry about.
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    // start at a power of two that's definitely no smaller than the root
+    let mut x = 1_u128 << (128 - n.leading_zeros()).div_ceil(2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+// Holding for h ms out of t wins when h * (t - h) > d, i.e. when h lies strictly between the
+// roots of h^2 - t*h + d = 0, which are (t +/- sqrt(t^2 - 4d)) / 2. The integer square root
+// gets us to within a step of the first winning hold time, then we nudge it into place. Races
+// are symmetric, so the last winning hold time mirrors the first. Everything is done in u128,
+// where t^2 can't overflow for any usize t.
+fn count_winning_options(race: &Race) -> usize {
+    let time = race.time_ms as u128;
+    let record = race.record_distance_mm as u128;
+    let wins = |hold: u128| hold * (time - hold) > record;
+
+    let Some(discriminant) = (time * time).checked_sub(4 * record) else {
+        return 0;
+    };
+    let mut first_win = (time - isqrt(discriminant)) / 2;
+    while first_win > 0 && wins(first_win - 1) {
+        first_win -= 1;
+    }
+    while first_win <= time / 2 && !wins(first_win) {
+        first_win += 1;
+    }
+    if first_win > time / 2 {
+        return 0;
+    }
+    let last_win = time - first_win;
+    (last_win - first_win + 1) as usize
+}
+
+#[derive(Debug, PartialEq)]
+struct RaceSolution {
+    num_winning_options: usize,
+    // the earliest hold time that goes furthest
+    optimal_hold_ms: usize,
+}
+
+// How far a boat goes, given how long the button was held. Every model's speed gain per ms
+// held never increases, which is what lets `solve_race` search instead of trying every option.
+trait BoatModel {
+    fn speed_mm_per_ms(&self, hold_ms: usize) -> u128;
+
+    fn distance_mm(&self, hold_ms: usize, time_ms: usize) -> u128 {
+        self.speed_mm_per_ms(hold_ms)
+            .saturating_mul((time_ms - hold_ms) as u128)
+    }
+
+    // models whose races can be solved without any searching override this
+    fn closed_form(&self, _race: &Race) -> Option<RaceSolution> {
+        None
+    }
+}
+
+// Reads a model from its name followed by its parameters, as given to `--model`.
+fn parse_model(args: &[String]) -> Result<Box<dyn BoatModel>, String> {
+    let usage = "usage is `--model linear <charge rate>`, \
+                 `--model capped <charge rate> <max speed>` \
+                 or `--model decaying <initial charge rate> <half life ms>`";
+    let Some((name, params)) = args.split_first() else {
+        return Err(usage.to_string());
+    };
+    let params = params
+        .iter()
+        .map(|s| s.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("model parameters must be whole numbers; {usage}"))?;
+    match (name.as_str(), params.as_slice()) {
+        ("linear", &[charge_rate]) => Ok(Box::new(LinearCharge { charge_rate })),
+        ("capped", &[charge_rate, max_speed]) => Ok(Box::new(CappedCharge {
+            charge_rate,
+            max_speed,
+        })),
+        ("decaying", &[initial_charge_rate, half_life_ms]) => Ok(Box::new(DecayingCharge {
+            initial_charge_rate,
+            half_life_ms,
+        })),
+        ("linear" | "capped" | "decaying", _) => Err(format!(
+            "wrong number of parameters for the {name} model; {usage}"
+        )),
+        _ => Err(format!("unknown boat model {name}; {usage}")),
+    }
+}
+
+// The puzzle's boat: every ms held adds the same speed.
+struct LinearCharge {
+    charge_rate: usize,
+}
+
+impl BoatModel for LinearCharge {
+    fn speed_mm_per_ms(&self, hold_ms: usize) -> u128 {
+        self.charge_rate as u128 * hold_ms as u128
+    }
+
+    // rate * h * (t - h) beats d exactly when h * (t - h) beats d / rate (rounded down)
+    fn closed_form(&self, race: &Race) -> Option<RaceSolution> {
+        let num_winning_options = match race.record_distance_mm.checked_div(self.charge_rate) {
+            Some(scaled_record) => count_winning_options(&Race {
+                time_ms: race.time_ms,
+                record_distance_mm: scaled_record,
+            }),
+            None => 0,
+        };
+        Some(RaceSolution {
+            num_winning_options,
+            optimal_hold_ms: if self.charge_rate == 0 {
+                0
+            } else {
+                race.time_ms / 2
+            },
+        })
+    }
+}
+
+// Charges linearly, but the boat can't go any faster than its max speed.
+struct CappedCharge {
+    charge_rate: usize,
+    max_speed: usize,
+}
+
+impl BoatModel for CappedCharge {
+    fn speed_mm_per_ms(&self, hold_ms: usize) -> u128 {
+        std::cmp::min(
+            self.charge_rate as u128 * hold_ms as u128,
+            self.max_speed as u128,
+        )
+    }
+}
+
+// The speed gained per ms held starts at the initial charge rate and halves every half life.
+struct DecayingCharge {
+    initial_charge_rate: usize,
+    half_life_ms: usize,
+}
+
+impl BoatModel for DecayingCharge {
+    fn speed_mm_per_ms(&self, hold_ms: usize) -> u128 {
+        // a half life of zero would make no sense, so treat it as the shortest possible one
+        let half_life_ms = std::cmp::max(self.half_life_ms, 1);
+        let full_half_lives = hold_ms / half_life_ms;
+        let mut speed: u128 = 0;
+        let mut charge_rate = self.initial_charge_rate as u128;
+        for _ in 0..full_half_lives {
+            if charge_rate == 0 {
+                break;
+            }
+            speed += charge_rate * half_life_ms as u128;
+            charge_rate /= 2;
+        }
+        speed + charge_rate * (hold_ms % half_life_ms) as u128
+    }
+}
+
+// Since speed gains per ms never increase, distances rise to a peak and then fall, never rising
+// again after levelling off. That means we can binary search for the peak, then binary search
+// either side of it for where the record starts and stops being beaten.
+fn solve_race(model: &dyn BoatModel, race: &Race) -> RaceSolution {
+    if let Some(solution) = model.closed_form(race) {
+        return solution;
+    }
+    let time_ms = race.time_ms;
+    let record = race.record_distance_mm as u128;
+    let distance = |hold_ms: usize| model.distance_mm(hold_ms, time_ms);
+    // first hold time where holding any longer doesn't help
+    let optimal_hold_ms = first_in_range(0, time_ms, |hold_ms| {
+        distance(hold_ms) >= distance(hold_ms + 1)
+    });
+    if distance(optimal_hold_ms) <= record {
+        return RaceSolution {
+            num_winning_options: 0,
+            optimal_hold_ms,
+        };
+    }
+    let first_win = first_in_range(0, optimal_hold_ms, |hold_ms| distance(hold_ms) > record);
+    let first_loss_after_peak = first_in_range(optimal_hold_ms, time_ms + 1, |hold_ms| {
+        distance(hold_ms) <= record
+    });
+    RaceSolution {
+        num_winning_options: first_loss_after_peak - first_win,
+        optimal_hold_ms,
+    }
+}
+
+// Binary search for the first value in start..end where the predicate holds, assuming it holds
+// for everything after that too. Returns end if it never holds.
+fn first_in_range(start: usize, end: usize, predicate: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (start, end);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if predicate(mid) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    low
+}
+
+// The same approach as `count_winning_options`, in arbitrary precision.
+fn count_winning_options_big(race: &BigRace) -> BigUint {
+    let time = &race.time_ms;
+    let record = &race.record_distance_mm;
+    let one = BigUint::from(1);
+    let wins = |hold: &BigUint| &(hold * &(time - hold)) > record;
+
+    let Some(discriminant) = (time * time).checked_sub(&(record << 2)) else {
+        return BigUint::zero();
+    };
+    let half_time = time >> 1;
+    let mut first_win = &(time - &discriminant.isqrt()) >> 1;
+    while !first_win.is_zero() && wins(&(&first_win - &one)) {
+        first_win = &first_win - &one;
+    }
+    while first_win <= half_time && !wins(&first_win) {
+        first_win = &first_win + &one;
+    }
+    if first_win > half_time {
+        return BigUint::zero();
+    }
+    let last_win = time - &first_win;
+    &(&last_win - &first_win) + &one
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_winning_options_brute_force(race: &Race) -> usize {
+        brute_force(&LinearCharge { charge_rate: 1 }, race).num_winning_options
+    }
+
+    fn brute_force(model: &dyn BoatModel, race: &Race) -> RaceSolution {
+        let mut num_winning_options = 0;
+        let mut optimal_hold_ms = 0;
+        for holdable_ms in 0..=race.time_ms {
+            let distance = model.distance_mm(holdable_ms, race.time_ms);
+            if distance > race.record_distance_mm as u128 {
+                num_winning_options += 1;
+            }
+            if distance > model.distance_mm(optimal_hold_ms, race.time_ms) {
+                optimal_hold_ms = holdable_ms;
+            }
+        }
+        RaceSolution {
+            num_winning_options,
+            optimal_hold_ms,
+        }
+    }
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(solve_part_1("resources/sample_1"), 288);
+        assert_eq!(solve_part_1("resources/input_1"), 2449062);
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(solve_part_2("resources/sample_1").to_string(), "71503");
+        assert_eq!(solve_part_2("resources/input_1").to_string(), "33149631");
+    }
+
+    #[test]
+    fn test_isqrt() {
+        for n in 0..10_000_u128 {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n);
+        }
+        for root in [u32::MAX as u128, u64::MAX as u128] {
+            let square = root * root;
+            assert_eq!(isqrt(square - 1), root - 1);
+            assert_eq!(isqrt(square), root);
+            assert_eq!(isqrt(square + 1), root);
+        }
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    // xorshift, so we don't need a crate just to generate races
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_closed_form_matches_brute_force() {
+        let mut rng = Rng(0x2023_0006);
+        for _ in 0..5_000 {
+            let time_ms = rng.below(500);
+            // records at and around the best possible distance are the interesting boundaries
+            let best = (time_ms / 2) * (time_ms - time_ms / 2);
+            let record_distance_mm = match rng.below(3) {
+                0 => best.saturating_sub(rng.below(3)),
+                1 => best + rng.below(2),
+                _ => rng.below(best + 1),
+            };
+            let race = Race {
+                time_ms,
+                record_distance_mm,
+            };
+            assert_eq!(
+                count_winning_options(&race),
+                count_winning_options_brute_force(&race),
+                "{race:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_closed_form_huge_race() {
+        // with t = 2m, holding for m - j ms goes m^2 - j^2 mm, so a record of m^2 - k^2 is
+        // beaten for exactly |j| < k; at this size f64 can't even represent the record exactly
+        let m: usize = 3_000_000_017;
+        let k: usize = 1_000;
+        let race = Race {
+            time_ms: 2 * m,
+            record_distance_mm: m * m - k * k,
+        };
+        assert_eq!(count_winning_options(&race), 2 * k - 1);
+        let race = Race {
+            time_ms: 2 * m,
+            record_distance_mm: m * m - k * k - 1,
+        };
+        assert_eq!(count_winning_options(&race), 2 * k + 1);
+    }
+
+    #[test]
+    fn test_big_matches_closed_form() {
+        let mut rng = Rng(0x2023_0034);
+        for _ in 0..2_000 {
+            let time_ms = rng.below(1 << 31);
+            let best = (time_ms / 2) * (time_ms - time_ms / 2);
+            let record_distance_mm = best.saturating_sub(rng.below(1 << 20));
+            let race = Race {
+                time_ms,
+                record_distance_mm,
+            };
+            let big_race = BigRace {
+                time_ms: BigUint::from(time_ms as u64),
+                record_distance_mm: BigUint::from(record_distance_mm as u64),
+            };
+            assert_eq!(
+                count_winning_options_big(&big_race),
+                BigUint::from(count_winning_options(&race) as u64),
+                "{race:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_big_race_hundreds_of_digits() {
+        // the same construction as `test_closed_form_huge_race`, with a 300-digit m
+        let m = "7".repeat(300).parse::<BigUint>().unwrap();
+        let k = "123456789123456789".parse::<BigUint>().unwrap();
+        let one = BigUint::from(1);
+        let two = BigUint::from(2);
+        let race = BigRace {
+            time_ms: &m * &two,
+            record_distance_mm: &(&m * &m) - &(&k * &k),
+        };
+        assert_eq!(count_winning_options_big(&race), &(&k * &two) - &one);
+        let race = BigRace {
+            time_ms: &m * &two,
+            record_distance_mm: &(&(&m * &m) - &(&k * &k)) - &one,
+        };
+        assert_eq!(count_winning_options_big(&race), &(&k * &two) + &one);
+    }
+
+    #[test]
+    fn test_boat_models_match_brute_force() {
+        let mut rng = Rng(0x2023_0035);
+        for _ in 0..2_000 {
+            let time_ms = rng.below(300);
+            let models: Vec<Box<dyn BoatModel>> = vec![
+                Box::new(LinearCharge {
+                    charge_rate: rng.below(5),
+                }),
+                Box::new(CappedCharge {
+                    charge_rate: 1 + rng.below(5),
+                    max_speed: rng.below(400),
+                }),
+                Box::new(DecayingCharge {
+                    initial_charge_rate: rng.below(100),
+                    half_life_ms: 1 + rng.below(40),
+                }),
+            ];
+            for model in models {
+                let best = (0..=time_ms)
+                    .map(|hold_ms| model.distance_mm(hold_ms, time_ms))
+                    .max()
+                    .unwrap() as usize;
+                let race = Race {
+                    time_ms,
+                    record_distance_mm: rng.below(best + 2),
+                };
+                let expected = brute_force(model.as_ref(), &race);
+                assert_eq!(solve_race(model.as_ref(), &race), expected, "{race:?}");
+            }
+        }
+    }
 
-    let mut num_winning_options = 0;
-    for holdable_ms in 0..race_time_ms {
-        let speed_mm_per_ms = holdable_ms;
-        let distance = speed_mm_per_ms * (race_time_ms - holdable_ms);
-        if distance > record_distance_mm {
-            num_winning_options += 1;
+    #[test]
+    fn test_parse_model() {
+        let args = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert!(parse_model(&args("linear 2")).is_ok());
+        assert!(parse_model(&args("capped 2 10")).is_ok());
+        assert!(parse_model(&args("decaying 8 3")).is_ok());
+        for bad_args in [
+            "",
+            "linear",
+            "capped 2",
+            "decaying 8 3 1",
+            "linear two",
+            "warp 9",
+        ] {
+            assert!(parse_model(&args(bad_args)).is_err(), "{bad_args}");
         }
     }
-    num_winning_options
 }
@@ -1,81 +1,67 @@
+mod poker;
+
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 enum HandType {
     HighCard,
     Pair,
     TwoPair,
     ThreeOfAKind,
+    // only possible in poker
+    Straight,
+    // only possible in poker
+    Flush,
     FullHouse,
     FourOfAKind,
+    // only possible in poker
+    StraightFlush,
+    // only possible in camel cards
     FiveOfAKind,
 }
 
 impl HandType {
-    fn from_cards(cards: &[Card; 5]) -> HandType {
+    // Works for hands of any size: the type only depends on the two biggest groups of matching
+    // cards, so e.g. six of a kind still counts as five of a kind. Wild cards join whichever
+    // group is already biggest.
+    fn from_cards(cards: &[Card], ruleset: &Ruleset) -> HandType {
         let mut counts_by_card: HashMap<&Card, usize> = HashMap::new();
+        let mut wild_count = 0;
         for card in cards {
-            *counts_by_card.entry(card).or_insert(0) += 1;
+            if ruleset.wild_cards.contains(card) {
+                wild_count += 1;
+            } else {
+                *counts_by_card.entry(card).or_insert(0) += 1;
+            }
         }
-        let joker_count = counts_by_card.get(&Card::Joker).cloned().unwrap_or(0);
-        counts_by_card.remove(&Card::Joker);
-        let mut card_counts = counts_by_card.values().collect::<Vec<_>>();
+        let mut card_counts = counts_by_card.values().cloned().collect::<Vec<_>>();
         // sort in descending order
         card_counts.sort_by(|a, b| b.cmp(a));
-        let max_card_count = if !card_counts.is_empty() {
-            card_counts[0] + joker_count
-        } else {
-            joker_count
-        };
+        let max_card_count = card_counts.first().unwrap_or(&0) + wild_count;
+        let secondary_card_count = card_counts.get(1).cloned().unwrap_or(0);
         use HandType::*;
         match max_card_count {
-            5 => FiveOfAKind,
+            5.. => FiveOfAKind,
             4 => FourOfAKind,
-            3 => {
-                let secondary_card_count = card_counts[1];
-                match secondary_card_count {
-                    2 => FullHouse,
-                    _ => ThreeOfAKind,
-                }
-            }
-            2 => {
-                let secondary_card_count = card_counts[1];
-                match secondary_card_count {
-                    2 => TwoPair,
-                    _ => Pair,
-                }
-            }
-            1 => HighCard,
-            _ => panic!("Unexpected card count {}", max_card_count),
+            3 => match secondary_card_count {
+                2.. => FullHouse,
+                _ => ThreeOfAKind,
+            },
+            2 => match secondary_card_count {
+                2 => TwoPair,
+                _ => Pair,
+            },
+            _ => HighCard,
         }
     }
-
-    fn to_num(self) -> usize {
-        use HandType::*;
-        match self {
-            HighCard => 1,
-            Pair => 2,
-            TwoPair => 3,
-            ThreeOfAKind => 4,
-            FullHouse => 5,
-            FourOfAKind => 6,
-            FiveOfAKind => 7,
-        }
-    }
-}
-
-impl Ord for HandType {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.to_num().cmp(&other.to_num())
-    }
 }
 
-#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, PartialOrd)]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 enum Card {
-    Joker,
     Two,
     Three,
     Four,
@@ -92,7 +78,7 @@ enum Card {
 }
 
 impl Card {
-    fn from_char(c: char, jacks_are_jokers: bool) -> Card {
+    fn from_char(c: char) -> Card {
         use Card::*;
         match c {
             '2' => Two,
@@ -104,13 +90,7 @@ impl Card {
             '8' => Eight,
             '9' => Nine,
             'T' => Ten,
-            'J' => {
-                if jacks_are_jokers {
-                    Joker
-                } else {
-                    Jack
-                }
-            }
+            'J' => Jack,
             'Q' => Queen,
             'K' => King,
             'A' => Ace,
@@ -118,36 +98,113 @@ impl Card {
         }
     }
 
-    fn to_num(self) -> usize {
-        use Card::*;
-        match self {
-            Joker => 1,
-            Two => 2,
-            Three => 3,
-            Four => 4,
-            Five => 5,
-            Six => 6,
-            Seven => 7,
-            Eight => 8,
-            Nine => 9,
-            Ten => 10,
-            Jack => 11,
-            Queen => 12,
-            King => 13,
-            Ace => 14,
-        }
+    fn to_char(self) -> char {
+        "23456789TJQKA".chars().nth(self as usize).unwrap()
     }
 }
 
-impl Ord for Card {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.to_num().cmp(&other.to_num())
+fn cards_to_string(cards: &[Card]) -> String {
+    cards.iter().map(|card| card.to_char()).collect()
+}
+
+// Everything that can vary between games of camel cards.
+#[derive(Debug)]
+struct Ruleset {
+    hand_size: usize,
+    wild_cards: Vec<Card>,
+    // weakest first; only used to break ties between hands of the same type
+    card_order: Vec<Card>,
+}
+
+impl Ruleset {
+    // Builds a ruleset from card chars, e.g. a card order of "23456789TJQKA".
+    fn new(hand_size: usize, card_order: &str, wild_cards: &str) -> Ruleset {
+        Ruleset {
+            hand_size,
+            wild_cards: wild_cards.chars().map(Card::from_char).collect(),
+            card_order: card_order.chars().map(Card::from_char).collect(),
+        }
+    }
+
+    // part 1
+    fn standard() -> Ruleset {
+        Ruleset::new(5, "23456789TJQKA", "")
+    }
+
+    // part 2: jacks become jokers, which are wild but the weakest card for breaking ties
+    fn jokers_wild() -> Ruleset {
+        Ruleset::new(5, "J23456789TQKA", "J")
+    }
+
+    fn card_strength(&self, card: Card) -> usize {
+        self.card_order
+            .iter()
+            .position(|&ranked| ranked == card)
+            .unwrap_or_else(|| panic!("Card {card:?} is not in this ruleset's card order"))
+    }
+
+    // Swaps each wild card for the card it ends up standing in for, which is the most common
+    // other card (the stronger one on ties), so that the result has the same type as the
+    // original hand. A hand of only wild cards becomes the strongest card that isn't wild.
+    fn resolve_wild_cards(&self, cards: &[Card]) -> Vec<Card> {
+        let mut counts_by_card: HashMap<Card, usize> = HashMap::new();
+        for &card in cards {
+            if !self.wild_cards.contains(&card) {
+                *counts_by_card.entry(card).or_insert(0) += 1;
+            }
+        }
+        let stand_in = counts_by_card
+            .into_iter()
+            .max_by_key(|&(card, count)| (count, self.card_strength(card)))
+            .map(|(card, _)| card)
+            .or_else(|| {
+                self.card_order
+                    .iter()
+                    .rev()
+                    .find(|card| !self.wild_cards.contains(card))
+                    .cloned()
+            });
+        cards
+            .iter()
+            .map(|&card| match stand_in {
+                Some(stand_in) if self.wild_cards.contains(&card) => stand_in,
+                _ => card,
+            })
+            .collect()
+    }
+
+    fn parse_hand(&self, line: &str) -> Hand {
+        let split = line.split(" ").collect::<Vec<_>>();
+        let bid = split[1].parse::<usize>().unwrap();
+        let cards = split[0].chars().map(Card::from_char).collect::<Vec<_>>();
+        if cards.len() != self.hand_size {
+            panic!(
+                "Hand {} should have {} cards but has {}",
+                split[0],
+                self.hand_size,
+                cards.len()
+            );
+        }
+        let hand_type = HandType::from_cards(&cards, self);
+        let card_strengths = cards.iter().map(|&card| self.card_strength(card)).collect();
+        let resolved_cards = self.resolve_wild_cards(&cards);
+        Hand {
+            cards,
+            resolved_cards,
+            card_strengths,
+            hand_type,
+            bid,
+        }
     }
 }
 
 #[derive(Debug)]
 struct Hand {
-    cards: [Card; 5],
+    cards: Vec<Card>,
+    // the cards after wild cards are swapped for what they stand in for
+    resolved_cards: Vec<Card>,
+    // each card's position in the ruleset's card order
+    card_strengths: Vec<usize>,
     hand_type: HandType,
     bid: usize,
 }
@@ -156,16 +213,10 @@ impl Ord for Hand {
     fn cmp(&self, other: &Self) -> Ordering {
         let hand_type_cmp = self.hand_type.cmp(&other.hand_type);
         if hand_type_cmp == Ordering::Equal {
-            for (self_card, other_card) in std::iter::zip(self.cards, &other.cards) {
-                let card_cmp = self_card.cmp(other_card);
-                if card_cmp != Ordering::Equal {
-                    return card_cmp;
-                }
-            }
+            self.card_strengths.cmp(&other.card_strengths)
         } else {
-            return hand_type_cmp;
+            hand_type_cmp
         }
-        Ordering::Equal
     }
 }
 
@@ -177,40 +228,26 @@ impl PartialOrd for Hand {
 
 impl PartialEq for Hand {
     fn eq(&self, other: &Self) -> bool {
-        self.hand_type == other.hand_type && self.cards == other.cards
+        self.hand_type == other.hand_type && self.card_strengths == other.card_strengths
     }
 }
 
 impl Eq for Hand {}
 
-fn solve(file_path: &str, jacks_are_jokers: bool) -> usize {
+// Every hand in the file, sorted from worst to best, so that a hand's rank is its index plus one.
+fn standings(file_path: &str, ruleset: &Ruleset) -> Vec<Hand> {
     let file = File::open(file_path).unwrap();
     let reader = BufReader::new(file);
     let mut hands: Vec<Hand> = Vec::new();
     for line in reader.lines() {
-        let line_content = &line.unwrap();
-        let split = line_content.split(" ").collect::<Vec<_>>();
-        let bid = split[1].parse::<usize>().unwrap();
-        let cards_vec = split[0]
-            .chars()
-            .map(|c| Card::from_char(c, jacks_are_jokers))
-            .collect::<Vec<_>>();
-        let cards = [
-            cards_vec[0],
-            cards_vec[1],
-            cards_vec[2],
-            cards_vec[3],
-            cards_vec[4],
-        ];
-        let hand_type = HandType::from_cards(&cards);
-        hands.push(Hand {
-            cards,
-            hand_type,
-            bid,
-        });
+        hands.push(ruleset.parse_hand(&line.unwrap()));
     }
-    // sort from worst hand to best
     hands.sort();
+    hands
+}
+
+fn solve(file_path: &str, ruleset: &Ruleset) -> usize {
+    let hands = standings(file_path, ruleset);
     let mut hand_winnings: Vec<usize> = Vec::new();
 
     for (i, hand) in hands.iter().enumerate() {
@@ -220,12 +257,188 @@ fn solve(file_path: &str, jacks_are_jokers: bool) -> usize {
     hand_winnings.iter().sum::<usize>()
 }
 
+fn display_standings(hands: &[Hand]) -> String {
+    let mut output = format!(
+        "{:>5}  {:<8}{:<10}{:<14}{:>6}{:>10}\n",
+        "Rank", "Hand", "Resolved", "Type", "Bid", "Winnings"
+    );
+    for (i, hand) in hands.iter().enumerate() {
+        let rank = i + 1;
+        output.push_str(&format!(
+            "{rank:>5}  {:<8}{:<10}{:<14}{:>6}{:>10}\n",
+            cards_to_string(&hand.cards),
+            cards_to_string(&hand.resolved_cards),
+            format!("{:?}", hand.hand_type),
+            hand.bid,
+            rank * hand.bid
+        ));
+    }
+    let total = hands
+        .iter()
+        .enumerate()
+        .map(|(i, hand)| (i + 1) * hand.bid)
+        .sum::<usize>();
+    output.push_str(&format!("Total winnings: {total}\n"));
+    output
+}
+
+fn standings_to_json(hands: &[Hand]) -> String {
+    let entries = hands
+        .iter()
+        .enumerate()
+        .map(|(i, hand)| {
+            format!(
+                "  {{\"rank\": {}, \"hand\": \"{}\", \"resolved\": \"{}\", \"type\": \"{:?}\", \"bid\": {}, \"winnings\": {}}}",
+                i + 1,
+                cards_to_string(&hand.cards),
+                cards_to_string(&hand.resolved_cards),
+                hand.hand_type,
+                hand.bid,
+                (i + 1) * hand.bid
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("[\n{}\n]\n", entries.join(",\n"))
+}
+
 fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    // `--poker "<cards>" ["<cards>"]` ranks real poker hands, e.g. "Ah Kd 2h 7h 9h Jh 2c"
+    if args.get(1).map(String::as_str) == Some("--poker") {
+        let mut hands = Vec::new();
+        for cards in &args[2..] {
+            let Some((hand, chosen)) =
+                poker::parse_cards(cards).and_then(|cards| poker::best_hand(&cards))
+            else {
+                println!("Error: {cards:?} should be at least five cards like Ah, Td or 2c");
+                return;
+            };
+            let chosen = chosen
+                .iter()
+                .map(|card| card.to_string())
+                .collect::<Vec<_>>();
+            println!("{}: {:?}", chosen.join(" "), hand.hand_type);
+            hands.push(hand);
+        }
+        if let [first, second] = hands.as_slice() {
+            match first.cmp(second) {
+                Ordering::Greater => println!("The first hand wins"),
+                Ordering::Less => println!("The second hand wins"),
+                Ordering::Equal => println!("The hands tie"),
+            }
+        }
+        return;
+    }
+    // `--standings text|json [<file>]` prints every hand's rank and winnings with jokers wild
+    if args.get(1).map(String::as_str) == Some("--standings") {
+        let file_path = args.get(3).map_or("resources/input_1", |s| s.as_str());
+        let hands = standings(file_path, &Ruleset::jokers_wild());
+        match args.get(2).map(String::as_str) {
+            Some("json") => print!("{}", standings_to_json(&hands)),
+            _ => print!("{}", display_standings(&hands)),
+        }
+        return;
+    }
+    // `<file> <hand size> <card order> [<wild cards>]` plays a custom game instead
+    if args.len() > 3 {
+        let ruleset = Ruleset::new(
+            args[2].parse::<usize>().unwrap(),
+            &args[3],
+            args.get(4).map_or("", |s| s.as_str()),
+        );
+        let solution = solve(&args[1], &ruleset);
+        println!("Total winnings: {solution}");
+        return;
+    }
+
     let file_path = "resources/input_1";
 
-    let part_1_solution = solve(file_path, false);
+    let part_1_solution = solve(file_path, &Ruleset::standard());
     println!("Part 1 solution: {part_1_solution}");
 
-    let part_2_solution = solve(file_path, true);
+    let part_2_solution = solve(file_path, &Ruleset::jokers_wild());
     println!("Part 2 solution: {part_2_solution}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(solve("resources/sample_1", &Ruleset::standard()), 6440);
+        assert_eq!(solve("resources/input_1", &Ruleset::standard()), 250254244);
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(solve("resources/sample_1", &Ruleset::jokers_wild()), 5905);
+        assert_eq!(
+            solve("resources/input_1", &Ruleset::jokers_wild()),
+            250087440
+        );
+    }
+
+    #[test]
+    fn test_custom_rulesets() {
+        let hand_type = |ruleset: &Ruleset, line: &str| ruleset.parse_hand(line).hand_type;
+
+        let three_card = Ruleset::new(3, "23456789TJQKA", "2");
+        assert_eq!(hand_type(&three_card, "KK2 1"), HandType::ThreeOfAKind);
+        assert_eq!(hand_type(&three_card, "K32 1"), HandType::Pair);
+        assert_eq!(hand_type(&three_card, "K43 1"), HandType::HighCard);
+
+        let seven_card = Ruleset::new(7, "23456789TJQKA", "");
+        assert_eq!(hand_type(&seven_card, "KKKAAA2 1"), HandType::FullHouse);
+        assert_eq!(hand_type(&seven_card, "KKKKKKA 1"), HandType::FiveOfAKind);
+        assert_eq!(hand_type(&seven_card, "KKQQ234 1"), HandType::TwoPair);
+
+        // several wild ranks, with aces ranked lowest
+        let custom = Ruleset::new(5, "A23456789TJQK", "QK");
+        assert_eq!(hand_type(&custom, "QK234 1"), HandType::ThreeOfAKind);
+        assert_eq!(hand_type(&custom, "QKQK2 1"), HandType::FiveOfAKind);
+        assert!(custom.parse_hand("A2345 1") < custom.parse_hand("23456 1"));
+    }
+
+    #[test]
+    fn test_resolve_wild_cards() {
+        let ruleset = Ruleset::jokers_wild();
+        let resolved = |line: &str| cards_to_string(&ruleset.parse_hand(line).resolved_cards);
+        assert_eq!(resolved("T55J5 1"), "T5555");
+        assert_eq!(resolved("KTJJT 1"), "KTTTT");
+        // ties go to the stronger card
+        assert_eq!(resolved("KKQQJ 1"), "KKQQK");
+        assert_eq!(resolved("JJJJJ 1"), "AAAAA");
+        assert_eq!(resolved("32T4K 1"), "32T4K");
+
+        // the resolved cards have the same type without any wild cards
+        for hand in standings("resources/input_1", &ruleset) {
+            let resolved_type = HandType::from_cards(&hand.resolved_cards, &Ruleset::standard());
+            assert_eq!(resolved_type, hand.hand_type);
+        }
+    }
+
+    #[test]
+    fn test_standings_report() {
+        let hands = standings("resources/sample_1", &Ruleset::jokers_wild());
+        let text = display_standings(&hands);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            " Rank  Hand    Resolved  Type             Bid  Winnings"
+        );
+        assert_eq!(
+            lines[5],
+            "    5  KTJJT   KTTTT     FourOfAKind      220      1100"
+        );
+        assert_eq!(lines[6], "Total winnings: 5905");
+
+        let json = standings_to_json(&hands);
+        assert!(json.starts_with(
+            "[\n  {\"rank\": 1, \"hand\": \"32T3K\", \"resolved\": \"32T3K\", \"type\": \"Pair\", \"bid\": 765, \"winnings\": 765},\n"
+        ));
+        assert!(json.ends_with(
+            "  {\"rank\": 5, \"hand\": \"KTJJT\", \"resolved\": \"KTTTT\", \"type\": \"FourOfAKind\", \"bid\": 220, \"winnings\": 1100}\n]\n"
+        ));
+    }
+}
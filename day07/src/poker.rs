@@ -0,0 +1,260 @@
+use super::{Card, Hand, HandType};
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+impl Suit {
+    const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+    fn to_char(self) -> char {
+        "cdhs".chars().nth(self as usize).unwrap()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct PlayingCard {
+    rank: Card,
+    suit: Suit,
+}
+
+impl PlayingCard {
+    // Parses a card like "Ah" or "Td".
+    fn parse(s: &str) -> Option<PlayingCard> {
+        let mut chars = s.chars();
+        let (rank_char, suit_char) = (chars.next()?, chars.next()?);
+        if chars.next().is_some() || !"23456789TJQKA".contains(rank_char) {
+            return None;
+        }
+        let suit = *Suit::ALL.iter().find(|suit| suit.to_char() == suit_char)?;
+        Some(PlayingCard {
+            rank: Card::from_char(rank_char),
+            suit,
+        })
+    }
+
+    // aces are high, at 14
+    fn rank_value(&self) -> usize {
+        self.rank as usize + 2
+    }
+}
+
+impl fmt::Display for PlayingCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.rank.to_char(), self.suit.to_char())
+    }
+}
+
+// Parses space-separated cards like "Ah Kd 2c".
+pub fn parse_cards(s: &str) -> Option<Vec<PlayingCard>> {
+    s.split_whitespace().map(PlayingCard::parse).collect()
+}
+
+// Ranks exactly five cards. The hand's card strengths hold rank values ordered for breaking
+// ties: bigger groups first, then higher ranks, so kickers come last. The ace in a wheel
+// (A-2-3-4-5) counts as a one.
+pub fn evaluate(cards: &[PlayingCard]) -> Hand {
+    assert_eq!(cards.len(), 5, "Poker hands have exactly five cards");
+    let mut counts_by_rank = [0; 15];
+    for card in cards {
+        counts_by_rank[card.rank_value()] += 1;
+    }
+    let mut groups = (2..15)
+        .filter(|&rank| counts_by_rank[rank] > 0)
+        .map(|rank| (rank, counts_by_rank[rank]))
+        .collect::<Vec<_>>();
+    groups.sort_by(|(a_rank, a_count), (b_rank, b_count)| {
+        b_count.cmp(a_count).then(b_rank.cmp(a_rank))
+    });
+    let mut card_strengths = groups
+        .iter()
+        .flat_map(|&(rank, count)| std::iter::repeat_n(rank, count))
+        .collect::<Vec<_>>();
+
+    let is_flush = cards.iter().all(|card| card.suit == cards[0].suit);
+    let is_straight = groups.len() == 5
+        && if card_strengths == [14, 5, 4, 3, 2] {
+            card_strengths = vec![5, 4, 3, 2, 1];
+            true
+        } else {
+            card_strengths[0] - card_strengths[4] == 4
+        };
+    let group_counts = groups.iter().map(|&(_, count)| count).collect::<Vec<_>>();
+
+    use HandType::*;
+    let hand_type = match (is_straight, is_flush, group_counts.as_slice()) {
+        (true, true, _) => StraightFlush,
+        (_, _, [4, 1]) => FourOfAKind,
+        (_, _, [3, 2]) => FullHouse,
+        (_, true, _) => Flush,
+        (true, _, _) => Straight,
+        (_, _, [3, 1, 1]) => ThreeOfAKind,
+        (_, _, [2, 2, 1]) => TwoPair,
+        (_, _, [2, 1, 1, 1]) => Pair,
+        _ => HighCard,
+    };
+    let ranks = cards.iter().map(|card| card.rank).collect::<Vec<_>>();
+    Hand {
+        cards: ranks.clone(),
+        resolved_cards: ranks,
+        card_strengths,
+        hand_type,
+        bid: 0,
+    }
+}
+
+// Tries every five-card combination (e.g. 21 of them out of seven cards) and keeps the best, or
+// gives None if there aren't five cards to make a hand from.
+pub fn best_hand(cards: &[PlayingCard]) -> Option<(Hand, Vec<PlayingCard>)> {
+    if cards.len() < 5 {
+        return None;
+    }
+    let mut best: Option<(Hand, Vec<PlayingCard>)> = None;
+    let mut indices: Vec<usize> = (0..5).collect();
+    loop {
+        let chosen = indices.iter().map(|&i| cards[i]).collect::<Vec<_>>();
+        let hand = evaluate(&chosen);
+        if best.as_ref().is_none_or(|(best_hand, _)| hand > *best_hand) {
+            best = Some((hand, chosen));
+        }
+        // step to the next combination in lexicographic order
+        let Some(i) = (0..5).rev().find(|&i| indices[i] < cards.len() - 5 + i) else {
+            return best;
+        };
+        indices[i] += 1;
+        for j in i + 1..5 {
+            indices[j] = indices[j - 1] + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+    use std::collections::HashMap;
+
+    fn hand(s: &str) -> Hand {
+        evaluate(&parse_cards(s).unwrap())
+    }
+
+    #[test]
+    fn test_hand_types() {
+        use HandType::*;
+        assert_eq!(hand("Ah Kh Qh Jh Th").hand_type, StraightFlush);
+        assert_eq!(hand("5d 4d 3d 2d Ad").hand_type, StraightFlush);
+        assert_eq!(hand("9c 9d 9h 9s 2c").hand_type, FourOfAKind);
+        assert_eq!(hand("9c 9d 9h 2s 2c").hand_type, FullHouse);
+        assert_eq!(hand("Ac 9c 7c 5c 2c").hand_type, Flush);
+        assert_eq!(hand("5d 4c 3h 2s Ad").hand_type, Straight);
+        assert_eq!(hand("Qd Kc Ah Js Td").hand_type, Straight);
+        assert_eq!(hand("Kd Ac 2h 3s 4d").hand_type, HighCard);
+        assert_eq!(hand("9c 9d 9h 3s 2c").hand_type, ThreeOfAKind);
+        assert_eq!(hand("9c 9d 3h 3s 2c").hand_type, TwoPair);
+        assert_eq!(hand("9c 9d 4h 3s 2c").hand_type, Pair);
+        assert_eq!(parse_cards("Ah Kx"), None);
+        assert_eq!(parse_cards("Ah 1h"), None);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        // the wheel is the lowest straight
+        assert!(hand("5d 4c 3h 2s Ad") < hand("6d 5c 4h 3s 2d"));
+        assert!(hand("5h 4h 3h 2h Ah") < hand("6c 5c 4c 3c 2c"));
+        // kickers break ties
+        assert!(hand("9c 9d Ah 3s 2c") > hand("9h 9s Kh Qs Jc"));
+        assert!(hand("9c 9d 4h 4s Ac") > hand("9h 9s 4d 4c Kc"));
+        assert!(hand("Ac Kc 9c 5c 3c") > hand("Ad Kd 9d 5d 2d"));
+        // suits never matter
+        assert_eq!(
+            hand("Ac Kd 9c 5c 3c").cmp(&hand("As Kh 9h 5h 3d")),
+            Ordering::Equal
+        );
+        // a full house is decided by its triple before its pair
+        assert!(hand("3c 3d 3h As Ac") < hand("4c 4d 4h 2s 2c"));
+    }
+
+    #[test]
+    fn test_best_of_seven() {
+        let (best, chosen) = best_hand(&parse_cards("Ah Kd 2h 7h 9h Jh 2c").unwrap()).unwrap();
+        assert_eq!(best.hand_type, HandType::Flush);
+        let chosen = chosen
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            chosen,
+            ["Ah", "2h", "7h", "9h", "Jh"].map(String::from).to_vec()
+        );
+
+        let (best, _) = best_hand(&parse_cards("Ah 2d 3c 4s 5h 5d 5c").unwrap()).unwrap();
+        assert_eq!(best.hand_type, HandType::Straight);
+
+        assert!(best_hand(&parse_cards("Ah Kd 2h 7h").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_all_five_card_hands() {
+        let deck = Suit::ALL
+            .iter()
+            .flat_map(|&suit| {
+                "23456789TJQKA".chars().map(move |c| PlayingCard {
+                    rank: Card::from_char(c),
+                    suit,
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut counts_by_type: HashMap<HandType, usize> = HashMap::new();
+        // which tie-break keys turn up for each hand type, as base-15 numbers
+        let mut seen_strengths = vec![vec![false; 15_usize.pow(5)]; 9];
+        for a in 0..52 {
+            for b in a + 1..52 {
+                for c in b + 1..52 {
+                    for d in c + 1..52 {
+                        for e in d + 1..52 {
+                            let cards = [deck[a], deck[b], deck[c], deck[d], deck[e]];
+                            let hand = evaluate(&cards);
+                            let key = hand
+                                .card_strengths
+                                .iter()
+                                .fold(0, |key, &strength| key * 15 + strength);
+                            seen_strengths[hand.hand_type as usize][key] = true;
+                            *counts_by_type.entry(hand.hand_type).or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+        // the well-known counts for each category out of all 2,598,960 hands
+        use HandType::*;
+        let expected = HashMap::from([
+            (StraightFlush, 40),
+            (FourOfAKind, 624),
+            (FullHouse, 3_744),
+            (Flush, 5_108),
+            (Straight, 10_200),
+            (ThreeOfAKind, 54_912),
+            (TwoPair, 123_552),
+            (Pair, 1_098_240),
+            (HighCard, 1_302_540),
+        ]);
+        assert_eq!(counts_by_type, expected);
+
+        // hands only tie when they're the same up to suits, which leaves the well-known 7,462
+        // distinct ranks, split between the categories like so
+        let distinct_by_type = seen_strengths
+            .iter()
+            .map(|seen| seen.iter().filter(|&&seen| seen).count())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            distinct_by_type,
+            [1_277, 2_860, 858, 858, 10, 1_277, 156, 156, 10]
+        );
+        assert_eq!(distinct_by_type.iter().sum::<usize>(), 7_462);
+    }
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::fs;
+use std::time::Instant;
 
 use regex::Regex;
 
@@ -19,100 +22,1015 @@ impl Instruction {
     }
 }
 
+#[derive(Debug, PartialEq)]
+enum NavigationError {
+    // the first line has no instructions on it
+    NoInstructions,
+    // an instruction that isn't L or R
+    BadInstruction {
+        c: char,
+    },
+    // a node line that doesn't look like `AAA = (BBB, CCC)`
+    BadNodeLine {
+        line: String,
+    },
+    // a node that's used but has no line of its own
+    MissingNode {
+        name: String,
+    },
+    // the walk from `start` repeated a (node, instruction index) state without ever passing an
+    // end node, so it's trapped in `cycle` (one node per move, starting at `stuck_at`)
+    Stuck {
+        start: String,
+        stuck_at: String,
+        cycle: Vec<String>,
+    },
+    // every ghost reaches end nodes, but never all on the same move
+    NeverAligned,
+    // the ghosts do line up, but not within a number of moves we can count
+    Overflow,
+}
+
+impl fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NavigationError::NoInstructions => write!(f, "There are no instructions"),
+            NavigationError::BadInstruction { c } => {
+                write!(f, "Instruction {c:?} should be L or R")
+            }
+            NavigationError::BadNodeLine { line } => {
+                write!(f, "Node line {line:?} should look like AAA = (BBB, CCC)")
+            }
+            NavigationError::MissingNode { name } => write!(f, "Node {name} has no entry"),
+            NavigationError::Stuck {
+                start,
+                stuck_at,
+                cycle,
+            } => {
+                write!(
+                    f,
+                    "The walk from {start} never reaches an end node: it gets stuck at {stuck_at} \
+                     in a {}-move cycle through {}",
+                    cycle.len(),
+                    cycle
+                        .iter()
+                        .take(8)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )?;
+                if cycle.len() > 8 {
+                    write!(f, " -> ...")?;
+                }
+                Ok(())
+            }
+            NavigationError::NeverAligned => {
+                write!(f, "The ghosts never all reach an end node at once")
+            }
+            NavigationError::Overflow => {
+                write!(f, "The ghosts only line up after too many moves to count")
+            }
+        }
+    }
+}
+
+// Index of a node in `Network::node_names`.
+type NodeId = usize;
+
+// Picks out the nodes that ghosts start or end on.
 #[derive(Debug)]
-struct NextNodes {
-    left: String,
-    right: String,
+enum NodeMatcher {
+    Prefix(String),
+    Suffix(String),
+    Names(Vec<String>),
 }
 
-fn main() {
-    let mut nodes_to_next_nodes: HashMap<String, NextNodes> = HashMap::new();
-    let re = Regex::new(r"^(\w{3}) = \((\w{3}), (\w{3})\)$").unwrap();
-    let file_content = fs::read_to_string("resources/input_1").unwrap();
-    let split = file_content.split("\n\n").collect::<Vec<_>>();
-    let instructions = split[0]
-        .chars()
-        .map(|c| Instruction::from_char(c).unwrap())
-        .collect::<Vec<_>>();
-    for line in split[1].lines() {
-        let caps = re.captures(line).unwrap();
-        nodes_to_next_nodes.insert(
-            caps[1].to_string(),
-            NextNodes {
-                left: caps[2].to_string(),
-                right: caps[3].to_string(),
-            },
-        );
-    }
-
-    let mut num_moves: usize = 0;
-    let instructions_len = instructions.len();
-    let mut curr_node = "AAA";
-    while curr_node != "ZZZ" {
-        let i = num_moves % instructions_len;
-        let next_nodes = nodes_to_next_nodes.get(curr_node).unwrap();
-        let instruction = instructions[i];
-        curr_node = match instruction {
-            Instruction::Left => &next_nodes.left,
-            Instruction::Right => &next_nodes.right,
-        };
-        num_moves += 1;
-    }
-    println!("Part 1 solution: {num_moves}");
-
-    // Solution is built on the assumption that every node is ultimately looping through
-    // the same route, and as long as we know the length of each of those routes, we can
-    // calculate when the routes align.
-    // Initialize 'current nodes' to starting nodes, aka nodes that end in 'A'
-    let mut current_nodes = nodes_to_next_nodes
-        .keys()
-        .filter(|k| k.chars().last().unwrap() == 'A')
-        .collect::<Vec<_>>();
-
-    let mut node_route_first_z_lens: Vec<Option<usize>> = vec![None; current_nodes.len()];
-    let mut num_moves: usize = 0;
-    let instructions_len = instructions.len();
-    while node_route_first_z_lens
-        .iter()
-        .any(|maybe_len| maybe_len.is_none())
-    {
-        let i = num_moves % instructions_len;
-        let instruction = instructions[i];
-        num_moves += 1;
-        current_nodes = current_nodes
+impl NodeMatcher {
+    // Parses `prefix:<text>`, `suffix:<text>` or a comma-separated list of node names.
+    fn parse(s: &str) -> NodeMatcher {
+        if let Some(prefix) = s.strip_prefix("prefix:") {
+            NodeMatcher::Prefix(prefix.to_string())
+        } else if let Some(suffix) = s.strip_prefix("suffix:") {
+            NodeMatcher::Suffix(suffix.to_string())
+        } else {
+            NodeMatcher::Names(s.split(',').map(|name| name.to_string()).collect())
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NodeMatcher::Prefix(prefix) => name.starts_with(prefix.as_str()),
+            NodeMatcher::Suffix(suffix) => name.ends_with(suffix.as_str()),
+            NodeMatcher::Names(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
+
+fn intern(
+    node_ids: &mut HashMap<String, NodeId>,
+    node_names: &mut Vec<String>,
+    name: &str,
+) -> NodeId {
+    *node_ids.entry(name.to_string()).or_insert_with(|| {
+        node_names.push(name.to_string());
+        node_names.len() - 1
+    })
+}
+
+// Node names are only needed while parsing and for output: everything else works on dense
+// node ids, so a step is just two array lookups.
+#[derive(Debug)]
+struct Network {
+    instructions: Vec<Instruction>,
+    node_names: Vec<String>,
+    node_ids: HashMap<String, NodeId>,
+    // indexed by node id, then by instruction (left first)
+    next_nodes: Vec<[NodeId; 2]>,
+}
+
+impl Network {
+    fn parse(file_content: &str) -> Result<Network, NavigationError> {
+        let re = Regex::new(r"^(\w+) = \((\w+), (\w+)\)$").unwrap();
+        let (instruction_line, node_lines) = file_content
+            .split_once("\n\n")
+            .unwrap_or((file_content, ""));
+        let instructions = instruction_line
+            .trim_end()
+            .chars()
+            .map(|c| Instruction::from_char(c).ok_or(NavigationError::BadInstruction { c }))
+            .collect::<Result<Vec<_>, _>>()?;
+        // every move looks its instruction up modulo the count
+        if instructions.is_empty() {
+            return Err(NavigationError::NoInstructions);
+        }
+        let mut node_ids: HashMap<String, NodeId> = HashMap::new();
+        let mut node_names: Vec<String> = Vec::new();
+        let mut edges: Vec<(NodeId, [NodeId; 2])> = Vec::new();
+        for line in node_lines.lines() {
+            let caps = re
+                .captures(line)
+                .ok_or_else(|| NavigationError::BadNodeLine {
+                    line: line.to_string(),
+                })?;
+            let node = intern(&mut node_ids, &mut node_names, &caps[1]);
+            let left = intern(&mut node_ids, &mut node_names, &caps[2]);
+            let right = intern(&mut node_ids, &mut node_names, &caps[3]);
+            edges.push((node, [left, right]));
+        }
+        let mut next_nodes: Vec<Option<[NodeId; 2]>> = vec![None; node_names.len()];
+        for (node, next) in edges {
+            next_nodes[node] = Some(next);
+        }
+        let next_nodes = next_nodes
             .iter()
             .enumerate()
-            .map(|(i, node)| {
-                let next_nodes = nodes_to_next_nodes.get(*node).unwrap();
-                let new_node = match instruction {
-                    Instruction::Left => &next_nodes.left,
-                    Instruction::Right => &next_nodes.right,
-                };
-                if new_node.chars().last().unwrap() == 'Z' {
-                    if node_route_first_z_lens[i].is_none() {
-                        node_route_first_z_lens[i] = Some(num_moves);
+            .map(|(node, next)| {
+                next.ok_or_else(|| NavigationError::MissingNode {
+                    name: node_names[node].clone(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Network {
+            instructions,
+            node_names,
+            node_ids,
+            next_nodes,
+        })
+    }
+
+    fn node_id(&self, name: &str) -> Result<NodeId, NavigationError> {
+        self.node_ids
+            .get(name)
+            .cloned()
+            .ok_or_else(|| NavigationError::MissingNode {
+                name: name.to_string(),
+            })
+    }
+
+    fn next_node(&self, node: NodeId, num_moves: usize) -> NodeId {
+        let instruction = self.instructions[num_moves % self.instructions.len()];
+        self.next_nodes[node][instruction as usize]
+    }
+
+    fn matching_nodes(&self, matcher: &NodeMatcher) -> Vec<NodeId> {
+        (0..self.node_names.len())
+            .filter(|&node| matcher.matches(&self.node_names[node]))
+            .collect()
+    }
+
+    fn end_flags(&self, ends: &NodeMatcher) -> Vec<bool> {
+        let mut is_end = vec![false; self.node_names.len()];
+        for node in self.matching_nodes(ends) {
+            is_end[node] = true;
+        }
+        is_end
+    }
+
+    // The nodes one ghost visits from `start` up to and including its first end node, or up to
+    // the point where it starts repeating itself if it never reaches one.
+    fn ghost_path(&self, start: NodeId, is_end: &[bool]) -> Vec<NodeId> {
+        let max_moves = self.node_names.len() * self.instructions.len();
+        let mut path = vec![start];
+        let mut curr_node = start;
+        for num_moves in 0..max_moves {
+            if is_end[curr_node] {
+                break;
+            }
+            curr_node = self.next_node(curr_node, num_moves);
+            path.push(curr_node);
+        }
+        path
+    }
+
+    // Tarjan's algorithm. Components come out in reverse topological order, and the recursion
+    // is only as deep as the longest simple path, which is fine for networks this size.
+    fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        struct Tarjan<'a> {
+            network: &'a Network,
+            next_index: usize,
+            indices: Vec<Option<usize>>,
+            low_links: Vec<usize>,
+            stack: Vec<NodeId>,
+            on_stack: Vec<bool>,
+            components: Vec<Vec<NodeId>>,
+        }
+
+        impl Tarjan<'_> {
+            fn visit(&mut self, node: NodeId) {
+                self.indices[node] = Some(self.next_index);
+                self.low_links[node] = self.next_index;
+                self.next_index += 1;
+                self.stack.push(node);
+                self.on_stack[node] = true;
+                for next in self.network.next_nodes[node] {
+                    match self.indices[next] {
+                        None => {
+                            self.visit(next);
+                            self.low_links[node] = self.low_links[node].min(self.low_links[next]);
+                        }
+                        Some(index) if self.on_stack[next] => {
+                            self.low_links[node] = self.low_links[node].min(index);
+                        }
+                        Some(_) => {}
                     }
                 }
-                new_node
+                if Some(self.low_links[node]) == self.indices[node] {
+                    let mut component = Vec::new();
+                    loop {
+                        let member = self.stack.pop().unwrap();
+                        self.on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    component.sort();
+                    self.components.push(component);
+                }
+            }
+        }
+
+        let num_nodes = self.node_names.len();
+        let mut tarjan = Tarjan {
+            network: self,
+            next_index: 0,
+            indices: vec![None; num_nodes],
+            low_links: vec![0; num_nodes],
+            stack: Vec::new(),
+            on_stack: vec![false; num_nodes],
+            components: Vec::new(),
+        };
+        for node in 0..num_nodes {
+            if tarjan.indices[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+        tarjan.components
+    }
+
+    // Renders the network in Graphviz's DOT format. Start nodes are green, end nodes are red,
+    // each strongly connected component with a cycle in it gets its own cluster, and the edges
+    // along `highlighted_path` are drawn in bold blue.
+    fn to_dot(&self, starts: &[NodeId], is_end: &[bool], highlighted_path: &[NodeId]) -> String {
+        let quoted = |node: NodeId| format!("\"{}\"", self.node_names[node]);
+        let mut dot = String::from("digraph network {\n");
+        for (node, &node_is_end) in is_end.iter().enumerate() {
+            let fill_color = if starts.contains(&node) {
+                Some("palegreen")
+            } else if node_is_end {
+                Some("lightcoral")
+            } else {
+                None
+            };
+            if let Some(fill_color) = fill_color {
+                dot.push_str(&format!(
+                    "  {} [style=filled, fillcolor={fill_color}];\n",
+                    quoted(node)
+                ));
+            }
+        }
+        let cyclic_components =
+            self.strongly_connected_components()
+                .into_iter()
+                .filter(|component| {
+                    component.len() > 1 || self.next_nodes[component[0]].contains(&component[0])
+                });
+        for (i, component) in cyclic_components.enumerate() {
+            dot.push_str(&format!("  subgraph cluster_{i} {{\n"));
+            dot.push_str(&format!("    label = \"component {i}\";\n"));
+            for &node in component.iter() {
+                dot.push_str(&format!("    {};\n", quoted(node)));
+            }
+            dot.push_str("  }\n");
+        }
+        // Moves along the path can be told apart by their instruction, so the highlighted edges
+        // are exactly the (from, to, instruction) triples the ghost used.
+        let highlighted_edges = highlighted_path
+            .windows(2)
+            .enumerate()
+            .map(|(num_moves, pair)| {
+                let instruction = self.instructions[num_moves % self.instructions.len()];
+                (pair[0], instruction as usize)
             })
             .collect::<Vec<_>>();
+        for node in 0..self.node_names.len() {
+            for (direction, label) in ["L", "R"].iter().enumerate() {
+                let next = self.next_nodes[node][direction];
+                let style = if highlighted_edges.contains(&(node, direction)) {
+                    ", color=blue, penwidth=2"
+                } else {
+                    ""
+                };
+                dot.push_str(&format!(
+                    "  {} -> {} [label=\"{label}\"{style}];\n",
+                    quoted(node),
+                    quoted(next)
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Walks from `start` until the (node, instruction index) state repeats. Since the next state
+    // only depends on the current one, the walk is periodic from then on.
+    fn find_cycle(&self, start: NodeId, is_end: &[bool]) -> GhostCycle {
+        let instructions_len = self.instructions.len();
+        let mut first_seen_at: Vec<Option<usize>> =
+            vec![None; self.node_names.len() * instructions_len];
+        let mut z_positions: Vec<usize> = Vec::new();
+        let mut path: Vec<NodeId> = Vec::new();
+        let mut curr_node = start;
+        let mut num_moves: usize = 0;
+        loop {
+            let state = curr_node * instructions_len + num_moves % instructions_len;
+            if let Some(offset) = first_seen_at[state] {
+                let (z_positions_before_cycle, z_positions_in_cycle) =
+                    z_positions.iter().partition(|&&position| position < offset);
+                return GhostCycle {
+                    offset,
+                    cycle_len: num_moves - offset,
+                    z_positions_before_cycle,
+                    z_positions_in_cycle,
+                    cycle_nodes: path.split_off(offset),
+                };
+            }
+            first_seen_at[state] = Some(num_moves);
+            path.push(curr_node);
+            if is_end[curr_node] {
+                z_positions.push(num_moves);
+            }
+            curr_node = self.next_node(curr_node, num_moves);
+            num_moves += 1;
+        }
+    }
+
+    fn stuck_error(&self, start: NodeId, cycle: &GhostCycle) -> NavigationError {
+        let names = |nodes: &[NodeId]| {
+            nodes
+                .iter()
+                .map(|&node| self.node_names[node].clone())
+                .collect::<Vec<_>>()
+        };
+        NavigationError::Stuck {
+            start: self.node_names[start].clone(),
+            stuck_at: self.node_names[cycle.cycle_nodes[0]].clone(),
+            cycle: names(&cycle.cycle_nodes),
+        }
     }
+}
+
+// Everything about when one ghost stands on a Z node. From move `offset` onwards its walk
+// repeats every `cycle_len` moves, so the Z positions in the cycle recur forever while the ones
+// before it only happen once.
+#[derive(Debug, PartialEq)]
+struct GhostCycle {
+    offset: usize,
+    cycle_len: usize,
+    z_positions_before_cycle: Vec<usize>,
+    // absolute move numbers in offset..offset + cycle_len
+    z_positions_in_cycle: Vec<usize>,
+    // where the ghost is on each move of the cycle
+    cycle_nodes: Vec<NodeId>,
+}
 
-    let route_lens = node_route_first_z_lens
+impl GhostCycle {
+    fn first_z(&self) -> Option<usize> {
+        self.z_positions_before_cycle
+            .first()
+            .or(self.z_positions_in_cycle.first())
+            .cloned()
+    }
+
+    fn is_at_z(&self, num_moves: usize) -> bool {
+        if num_moves < self.offset {
+            self.z_positions_before_cycle.contains(&num_moves)
+        } else {
+            let position_in_cycle = self.offset + (num_moves - self.offset) % self.cycle_len;
+            self.z_positions_in_cycle.contains(&position_in_cycle)
+        }
+    }
+}
+
+// Returns (g, x, y) such that a * x + b * y = g = gcd(a, b).
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+// Generalised Chinese Remainder Theorem: merges x ≡ a1 (mod m1) and x ≡ a2 (mod m2) into a
+// single congruence modulo lcm(m1, m2). The moduli don't need to be coprime, but then the
+// residues have to agree modulo their gcd, or no x satisfies both (which is Ok(None)).
+fn combine_congruences(
+    (a1, m1): (i128, i128),
+    (a2, m2): (i128, i128),
+) -> Result<Option<(i128, i128)>, NavigationError> {
+    let (g, x, _) = extended_gcd(m1, m2);
+    if (a2 - a1) % g != 0 {
+        return Ok(None);
+    }
+    let lcm = (m1 / g).checked_mul(m2).ok_or(NavigationError::Overflow)?;
+    let step = ((a2 - a1) / g)
+        .checked_mul(x)
+        .ok_or(NavigationError::Overflow)?
+        .rem_euclid(m2 / g);
+    let residue = m1
+        .checked_mul(step)
+        .and_then(|product| a1.checked_add(product))
+        .ok_or(NavigationError::Overflow)?;
+    Ok(Some((residue.rem_euclid(lcm), lcm)))
+}
+
+// The first move on which every ghost stands on a Z node at once, or None if that never happens.
+fn first_common_z(cycles: &[GhostCycle]) -> Result<Option<usize>, NavigationError> {
+    // Before the last ghost enters its cycle, that ghost can only be on a Z at one of its
+    // one-off positions, so checking every one-off position covers this stretch.
+    let from_one_offs = cycles
         .iter()
-        .map(|n| n.unwrap())
-        .collect::<Vec<_>>();
+        .flat_map(|cycle| cycle.z_positions_before_cycle.iter())
+        .filter(|&&num_moves| cycles.iter().all(|cycle| cycle.is_at_z(num_moves)))
+        .min()
+        .cloned();
+
+    // After that, every ghost is cycling, so we need one Z position per ghost whose
+    // congruences can all be satisfied at once. Folding the ghosts in one at a time leaves every
+    // congruence modulo the same running lcm, so only the distinct residues are worth keeping.
+    let Some(max_offset) = cycles.iter().map(|cycle| cycle.offset).max() else {
+        return Ok(None);
+    };
+    let max_offset = max_offset as i128;
+    let mut congruences: Vec<(i128, i128)> = vec![(0, 1)];
+    for cycle in cycles {
+        let mut merged = Vec::new();
+        for &congruence in congruences.iter() {
+            for &position in cycle.z_positions_in_cycle.iter() {
+                let z_congruence = (position as i128, cycle.cycle_len as i128);
+                if let Some(combined) = combine_congruences(congruence, z_congruence)? {
+                    merged.push(combined);
+                }
+            }
+        }
+        merged.sort();
+        merged.dedup();
+        congruences = merged;
+    }
+    let mut from_cycles: Option<usize> = None;
+    for &(residue, modulus) in congruences.iter() {
+        // the first move no earlier than max_offset with the right residue
+        let num_moves = ((max_offset - residue + modulus - 1).max(0) / modulus)
+            .checked_mul(modulus)
+            .and_then(|skipped| residue.checked_add(skipped))
+            .and_then(|num_moves| usize::try_from(num_moves).ok())
+            .ok_or(NavigationError::Overflow)?;
+        from_cycles = Some(from_cycles.map_or(num_moves, |best| best.min(num_moves)));
+    }
+
+    Ok(match (from_one_offs, from_cycles) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    })
+}
+
+fn solve_part_1(network: &Network) -> Result<usize, NavigationError> {
+    let start = network.node_id("AAA")?;
+    let mut is_end = vec![false; network.node_names.len()];
+    is_end[network.node_id("ZZZ")?] = true;
+    let cycle = network.find_cycle(start, &is_end);
+    cycle
+        .first_z()
+        .ok_or_else(|| network.stuck_error(start, &cycle))
+}
+
+// A ghost starts on every node that `starts` matches. Rather than assuming each ghost loops back
+// to its first end node at a fixed interval, we find each ghost's actual cycle and solve the
+// resulting congruences.
+fn solve_part_2(
+    network: &Network,
+    starts: &NodeMatcher,
+    ends: &NodeMatcher,
+) -> Result<usize, NavigationError> {
+    let is_end = network.end_flags(ends);
+    let mut cycles: Vec<GhostCycle> = Vec::new();
+    for start in network.matching_nodes(starts) {
+        let cycle = network.find_cycle(start, &is_end);
+        if cycle.first_z().is_none() {
+            return Err(network.stuck_error(start, &cycle));
+        }
+        cycles.push(cycle);
+    }
+    first_common_z(&cycles)?.ok_or(NavigationError::NeverAligned)
+}
+
+// Times `num_steps` moves from the first node, once over the interned adjacency array and once
+// looking every node up by name like the original solution did.
+fn benchmark_steps(network: &Network, num_steps: usize) {
+    let start = Instant::now();
+    let mut curr_node: NodeId = 0;
+    for num_moves in 0..num_steps {
+        curr_node = network.next_node(curr_node, num_moves);
+    }
+    let interned_elapsed = start.elapsed();
+    let interned_end = &network.node_names[curr_node];
+
+    let nodes_to_next_nodes = (0..network.node_names.len())
+        .map(|node| {
+            let [left, right] = network.next_nodes[node];
+            (
+                network.node_names[node].clone(),
+                (
+                    network.node_names[left].clone(),
+                    network.node_names[right].clone(),
+                ),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let start = Instant::now();
+    let mut curr_node = &network.node_names[0];
+    for num_moves in 0..num_steps {
+        let (left, right) = &nodes_to_next_nodes[curr_node];
+        curr_node = match network.instructions[num_moves % network.instructions.len()] {
+            Instruction::Left => left,
+            Instruction::Right => right,
+        };
+    }
+    let by_name_elapsed = start.elapsed();
+    assert_eq!(interned_end, curr_node);
+
+    for (label, elapsed) in [("interned", interned_elapsed), ("by name", by_name_elapsed)] {
+        let steps_per_second = num_steps as f64 / elapsed.as_secs_f64();
+        println!("{label}: {num_steps} steps in {elapsed:?} ({steps_per_second:.0} steps/s)");
+    }
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    // `--dot` and `--ghosts` can read another file
+    let file_path = match args.get(1).map(String::as_str) {
+        Some("--dot") | Some("--ghosts") => args.get(4).map_or("resources/input_1", |s| s.as_str()),
+        _ => "resources/input_1",
+    };
+    let network = match Network::parse(&fs::read_to_string(file_path).unwrap()) {
+        Ok(network) => network,
+        Err(err) => {
+            println!("Error: {err}");
+            return;
+        }
+    };
+
+    // `--bench <num steps>` measures how fast we can walk the network
+    if args.len() > 1 && args[1] == "--bench" {
+        match args.get(2).map(|num_steps| num_steps.parse()) {
+            Some(Ok(num_steps)) => benchmark_steps(&network, num_steps),
+            _ => println!("Error: usage is `--bench <num steps>`"),
+        }
+        return;
+    }
+    // `--dot <path> [<start node> [<file>]]` writes the network out for Graphviz, highlighting
+    // the path from the given start node to its first Z
+    if args.len() > 2 && args[1] == "--dot" {
+        let starts = network.matching_nodes(&NodeMatcher::Suffix("A".to_string()));
+        let is_end = network.end_flags(&NodeMatcher::Suffix("Z".to_string()));
+        let highlighted_path = match args.get(3).map(|name| network.node_id(name)) {
+            Some(Ok(start)) => network.ghost_path(start, &is_end),
+            Some(Err(err)) => {
+                println!("Error: {err}");
+                return;
+            }
+            None => Vec::new(),
+        };
+        fs::write(
+            &args[2],
+            network.to_dot(&starts, &is_end, &highlighted_path),
+        )
+        .unwrap();
+        return;
+    }
+    // `--ghosts <starts> <ends> [<file>]` runs part 2 with other start and end nodes, where each
+    // is `prefix:<text>`, `suffix:<text>` or a comma-separated list of names
+    if args.len() > 3 && args[1] == "--ghosts" {
+        let starts = NodeMatcher::parse(&args[2]);
+        let ends = NodeMatcher::parse(&args[3]);
+        match solve_part_2(&network, &starts, &ends) {
+            Ok(num_moves) => println!("All ghosts reach an end node after {num_moves} moves"),
+            Err(err) => println!("Error: {err}"),
+        }
+        return;
+    }
+
+    match solve_part_1(&network) {
+        Ok(part_1_solution) => println!("Part 1 solution: {part_1_solution}"),
+        Err(err) => println!("Part 1 error: {err}"),
+    }
+
+    let starts = NodeMatcher::Suffix("A".to_string());
+    let ends = NodeMatcher::Suffix("Z".to_string());
+    match solve_part_2(&network, &starts, &ends) {
+        Ok(part_2_solution) => println!("Part 2 solution: {part_2_solution}"),
+        Err(err) => println!("Part 2 error: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(file_path: &str) -> Network {
+        Network::parse(&fs::read_to_string(file_path).unwrap()).unwrap()
+    }
+
+    fn solve_ghosts(network: &Network) -> Result<usize, NavigationError> {
+        solve_part_2(
+            network,
+            &NodeMatcher::parse("suffix:A"),
+            &NodeMatcher::parse("suffix:Z"),
+        )
+    }
+
+    // Moves every ghost together until they're all on a Z node, giving up after `max_moves`.
+    fn brute_force(network: &Network, max_moves: usize) -> Option<usize> {
+        let mut current_nodes = network.matching_nodes(&NodeMatcher::parse("suffix:A"));
+        for num_moves in 0..=max_moves {
+            if current_nodes
+                .iter()
+                .all(|&node| network.node_names[node].ends_with('Z'))
+            {
+                return Some(num_moves);
+            }
+            current_nodes = current_nodes
+                .iter()
+                .map(|&node| network.next_node(node, num_moves))
+                .collect();
+        }
+        None
+    }
+
+    #[test]
+    fn test_part_one() {
+        assert_eq!(solve_part_1(&network("resources/sample_1")), Ok(2));
+        assert_eq!(solve_part_1(&network("resources/sample_2")), Ok(6));
+        assert_eq!(solve_part_1(&network("resources/input_1")), Ok(19199));
+    }
+
+    #[test]
+    fn test_part_two() {
+        assert_eq!(solve_ghosts(&network("resources/sample_3")), Ok(6));
+        assert_eq!(
+            solve_ghosts(&network("resources/input_1")),
+            Ok(13663968099527)
+        );
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let network = network("resources/sample_3");
+        let is_end = network.end_flags(&NodeMatcher::parse("suffix:Z"));
+        assert_eq!(
+            network.find_cycle(network.node_ids["22A"], &is_end),
+            GhostCycle {
+                offset: 1,
+                cycle_len: 6,
+                z_positions_before_cycle: vec![],
+                z_positions_in_cycle: vec![3, 6],
+                cycle_nodes: ["22B", "22C", "22Z", "22B", "22C", "22Z"]
+                    .iter()
+                    .map(|name| network.node_ids[*name])
+                    .collect(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_node_names_and_matchers() {
+        let file_content = "\
+LR
+
+start_one = (middle, middle)
+start_two = (middle, other)
+middle = (other, goal)
+other = (goal, goal)
+goal = (middle, middle)
+";
+        let network = Network::parse(file_content).unwrap();
+        assert_eq!(network.node_names.len(), 5);
+        assert_eq!(
+            network.next_nodes[network.node_ids["middle"]],
+            [network.node_ids["other"], network.node_ids["goal"]]
+        );
+
+        let starts = NodeMatcher::parse("prefix:start_");
+        let ends = NodeMatcher::parse("goal");
+        assert_eq!(network.matching_nodes(&starts).len(), 2);
+        // both ghosts go left to middle, then right to goal
+        assert_eq!(solve_part_2(&network, &starts, &ends), Ok(2));
+        let ends = NodeMatcher::parse("other,goal");
+        assert_eq!(solve_part_2(&network, &starts, &ends), Ok(2));
+        let ends = NodeMatcher::parse("suffix:her");
+        assert_eq!(
+            solve_part_2(&network, &starts, &ends),
+            Err(NavigationError::Stuck {
+                start: "start_one".to_string(),
+                stuck_at: "middle".to_string(),
+                cycle: vec!["middle".to_string(), "goal".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_dot_export() {
+        let network = network("resources/sample_3");
+        let starts = network.matching_nodes(&NodeMatcher::parse("suffix:A"));
+        let is_end = network.end_flags(&NodeMatcher::parse("suffix:Z"));
+        let names = |nodes: Vec<NodeId>| {
+            nodes
+                .iter()
+                .map(|&node| network.node_names[node].as_str())
+                .collect::<Vec<_>>()
+        };
+
+        let path = network.ghost_path(network.node_ids["11A"], &is_end);
+        assert_eq!(names(path.clone()), ["11A", "11B", "11Z"]);
+        let mut components = network
+            .strongly_connected_components()
+            .into_iter()
+            .map(names)
+            .collect::<Vec<_>>();
+        components.sort();
+        assert_eq!(
+            components,
+            vec![
+                vec!["11A"],
+                vec!["11B", "11Z"],
+                vec!["22A"],
+                vec!["22B", "22C", "22Z"],
+                vec!["XXX"],
+            ]
+        );
+
+        let dot = network.to_dot(&starts, &is_end, &path);
+        assert!(dot.starts_with("digraph network {\n"));
+        assert!(dot.contains("  \"11A\" [style=filled, fillcolor=palegreen];\n"));
+        assert!(dot.contains("  \"22Z\" [style=filled, fillcolor=lightcoral];\n"));
+        // the two paths to 11Z are told apart by instruction
+        assert!(dot.contains("  \"11A\" -> \"11B\" [label=\"L\", color=blue, penwidth=2];\n"));
+        assert!(dot.contains("  \"11B\" -> \"11Z\" [label=\"R\", color=blue, penwidth=2];\n"));
+        assert!(dot.contains("  \"11B\" -> \"XXX\" [label=\"L\"];\n"));
+        // XXX only loops back to itself, but that's still a cycle worth a cluster
+        assert_eq!(dot.matches("subgraph cluster_").count(), 3);
+        assert_eq!(dot.matches(" -> ").count(), 2 * network.node_names.len());
+    }
+
+    #[test]
+    fn test_navigation_errors() {
+        let file_content = "\
+L
+
+AAA = (BBB, ZZZ)
+ZZZ = (ZZZ, ZZZ)
+";
+        assert_eq!(
+            Network::parse(file_content).unwrap_err(),
+            NavigationError::MissingNode {
+                name: "BBB".to_string()
+            }
+        );
+        assert_eq!(
+            solve_part_1(&network("resources/sample_3")),
+            Err(NavigationError::MissingNode {
+                name: "AAA".to_string()
+            })
+        );
+
+        let file_content = "\
+L
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)
+";
+        let err = solve_part_1(&Network::parse(file_content).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The walk from AAA never reaches an end node: it gets stuck at AAA in a 2-move cycle \
+             through AAA -> BBB"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Network::parse("\n\nAAA = (AAA, AAA)\n").unwrap_err(),
+            NavigationError::NoInstructions
+        );
+        assert_eq!(
+            Network::parse("").unwrap_err(),
+            NavigationError::NoInstructions
+        );
+        assert_eq!(
+            Network::parse("LRX\n\nAAA = (AAA, AAA)\n").unwrap_err(),
+            NavigationError::BadInstruction { c: 'X' }
+        );
+        assert_eq!(
+            Network::parse("LR\n\nAAA = (AAA, AAA)\nBBB = AAA\n").unwrap_err(),
+            NavigationError::BadNodeLine {
+                line: "BBB = AAA".to_string()
+            }
+        );
+        // no blank line means no nodes, so there's nowhere to start
+        let network = Network::parse("LR\n").unwrap();
+        assert_eq!(
+            solve_part_1(&network),
+            Err(NavigationError::MissingNode {
+                name: "AAA".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_combine_congruences() {
+        assert_eq!(combine_congruences((2, 3), (3, 5)), Ok(Some((8, 15))));
+        // moduli that share a factor
+        assert_eq!(combine_congruences((1, 4), (3, 6)), Ok(Some((9, 12))));
+        assert_eq!(combine_congruences((0, 4), (1, 6)), Ok(None));
+        // coprime moduli whose lcm doesn't fit
+        assert_eq!(
+            combine_congruences((0, i128::MAX), (1, i128::MAX - 1)),
+            Err(NavigationError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_many_z_positions() {
+        // Six ghosts with ten Z positions each would be a million combinations, but they all
+        // share a cycle length, so there are never more than 30 distinct residues.
+        let cycles = (0..6)
+            .map(|ghost| GhostCycle {
+                offset: ghost,
+                cycle_len: 30,
+                z_positions_before_cycle: vec![],
+                z_positions_in_cycle: (0..10).map(|i| ghost + (ghost + 2 * i) % 30).collect(),
+                cycle_nodes: vec![0; 30],
+            })
+            .collect::<Vec<_>>();
+        let expected = (0..1_000).find(|&num_moves| {
+            num_moves >= 5 && cycles.iter().all(|cycle| cycle.is_at_z(num_moves))
+        });
+        assert!(expected.is_some());
+        assert_eq!(first_common_z(&cycles), Ok(expected));
+
+        // cycles whose lengths multiply out past usize
+        let cycles = [1_000_000_007, 1_000_000_009, 998_244_353]
+            .iter()
+            .map(|&cycle_len| GhostCycle {
+                offset: 0,
+                cycle_len,
+                z_positions_before_cycle: vec![],
+                z_positions_in_cycle: vec![cycle_len - 1],
+                cycle_nodes: vec![],
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(first_common_z(&cycles), Err(NavigationError::Overflow));
+    }
+
+    #[test]
+    fn test_unaligned_cycles() {
+        // The first ghost passes a one-off Z before settling into a cycle with two Zs that
+        // doesn't start at move zero, and the ghosts only line up on that one-off Z.
+        let file_content = "\
+L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11C, 11C)
+11C = (11Y, 11Y)
+11Y = (11B, 11B)
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (22B, 22B)
+";
+        assert_eq!(solve_ghosts(&Network::parse(file_content).unwrap()), Ok(1));
+
+        // A ghost that never reaches a Z gets stuck.
+        let file_content = "\
+L
+
+11A = (11B, 11B)
+11B = (11A, 11A)
+22A = (22Z, 22Z)
+22Z = (22A, 22A)
+";
+        assert_eq!(
+            solve_ghosts(&Network::parse(file_content).unwrap()),
+            Err(NavigationError::Stuck {
+                start: "11A".to_string(),
+                stuck_at: "11A".to_string(),
+                cycle: vec!["11A".to_string(), "11B".to_string()],
+            })
+        );
+
+        // Both ghosts reach Zs, but one only on odd moves and the other only on even ones.
+        let file_content = "\
+L
+
+11A = (11Z, 11Z)
+11Z = (11A, 11A)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22B, 22B)
+";
+        assert_eq!(
+            solve_ghosts(&Network::parse(file_content).unwrap()),
+            Err(NavigationError::NeverAligned)
+        );
+    }
+
+    // xorshift, so we don't need a crate just to generate test networks
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
 
-    // starting from max route len, brute-force the least common multiple (?)
-    // note: this would be slightly faster if I removed the max number so I don't unnecessarily
-    // check it every time
-    let max_route_len = route_lens.iter().max().unwrap();
-    let mut curr_route_len = 0;
-    loop {
-        curr_route_len += max_route_len;
-        if route_lens.iter().all(|len| curr_route_len % len == 0) {
-            break;
+    #[test]
+    fn test_random_networks() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..300 {
+            let num_nodes = 2 + rng.below(12);
+            let suffixes = ['A', 'Z', 'X'];
+            let names = (0..num_nodes)
+                // the first node always starts a ghost
+                .map(|i| {
+                    format!(
+                        "{}{i}{}",
+                        "n".repeat(rng.below(3)),
+                        suffixes[if i == 0 { 0 } else { rng.below(3) }]
+                    )
+                })
+                .collect::<Vec<_>>();
+            let instructions = (0..1 + rng.below(4))
+                .map(|_| if rng.below(2) == 0 { 'L' } else { 'R' })
+                .collect::<String>();
+            let mut file_content = format!("{instructions}\n\n");
+            for name in &names {
+                let left = &names[rng.below(num_nodes)];
+                let right = &names[rng.below(num_nodes)];
+                file_content.push_str(&format!("{name} = ({left}, {right})\n"));
+            }
+            let network = Network::parse(&file_content).unwrap();
+            let max_moves = 5_000;
+            match (solve_ghosts(&network), brute_force(&network, max_moves)) {
+                (solution, Some(expected)) => assert_eq!(solution, Ok(expected)),
+                (Ok(solution), None) => assert!(solution > max_moves),
+                (Err(_), None) => {}
+            }
         }
     }
-    println!("Part 2 solution: {curr_route_len}");
 }
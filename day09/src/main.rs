@@ -1,65 +1,538 @@
+mod rational;
+
+use rational::{gcd, Rational};
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-fn generate_diffs(readings: &[i64]) -> Vec<i64> {
-    readings
-        .windows(2)
-        .map(|slice| {
-            if slice.len() == 2 {
-                slice[1] - slice[0]
-            } else if slice.len() == 1 {
-                slice[0]
-            } else {
-                panic!("Ran out of items in slice!");
-            }
-        })
-        .collect::<Vec<_>>()
+#[derive(Debug, PartialEq)]
+enum HistoryError {
+    Empty,
+    // the difference table ran out of readings before reaching a row of zeros, after getting
+    // down to the row of degree_reached-th differences
+    DoesNotConverge { degree_reached: usize },
+    Overflow,
 }
 
-fn infer_last_reading(readings: &[i64]) -> i64 {
-    // base case
-    if readings.iter().all(|r| *r == 0) {
-        return 0;
-    } else {
-        let diffs = generate_diffs(readings);
-        return readings.iter().last().unwrap() + infer_last_reading(&diffs);
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HistoryError::Empty => write!(f, "The history has no readings"),
+            HistoryError::DoesNotConverge { degree_reached } => write!(
+                f,
+                "The differences never reach a row of zeros: the table ran out of readings at \
+                 degree {degree_reached}"
+            ),
+            HistoryError::Overflow => write!(f, "A value doesn't fit in 128 bits"),
+        }
     }
 }
 
-fn infer_first_reading(readings: &[i64]) -> i64 {
-    // base case
-    if readings.iter().all(|r| *r == 0) {
-        return 0;
-    } else {
-        let diffs = generate_diffs(readings);
-        return readings[0] - infer_first_reading(&diffs);
+// Overwrites a row of the difference table with the row below it, which is one entry shorter,
+// so the last entry is left over as scratch space.
+fn generate_diffs_in_place(row: &mut [i128]) -> Result<(), HistoryError> {
+    for i in 0..row.len().saturating_sub(1) {
+        row[i] = row[i + 1]
+            .checked_sub(row[i])
+            .ok_or(HistoryError::Overflow)?;
     }
+    Ok(())
 }
 
-fn main() {
-    let file = File::open("resources/input_1").unwrap();
+// The first and last entry of each row of the difference table, from the readings themselves
+// down to (but not including) the first row of zeros. The rows are worked out one after another
+// in a single buffer, so this needs O(n) memory however deep the table goes.
+fn difference_table_edges(readings: &[i128]) -> Result<Vec<(i128, i128)>, HistoryError> {
+    if readings.is_empty() {
+        return Err(HistoryError::Empty);
+    }
+    let mut edges: Vec<(i128, i128)> = Vec::new();
+    let mut diffs = readings.to_vec();
+    let mut row_len = diffs.len();
+    while diffs[..row_len].iter().any(|&diff| diff != 0) {
+        // a single nonzero value has no differences left to check
+        if row_len == 1 {
+            return Err(HistoryError::DoesNotConverge {
+                degree_reached: edges.len(),
+            });
+        }
+        edges.push((diffs[0], diffs[row_len - 1]));
+        generate_diffs_in_place(&mut diffs[..row_len])?;
+        row_len -= 1;
+    }
+    Ok(edges)
+}
+
+fn infer_last_reading(readings: &[i128]) -> Result<i128, HistoryError> {
+    // each row's next value is its last value plus the next value of the row below
+    difference_table_edges(readings)?
+        .iter()
+        .try_fold(0_i128, |below, &(_, last)| last.checked_add(below))
+        .ok_or(HistoryError::Overflow)
+}
+
+fn infer_first_reading(readings: &[i128]) -> Result<i128, HistoryError> {
+    // each row's previous value is its first value minus the previous value of the row below
+    difference_table_edges(readings)?
+        .iter()
+        .rev()
+        .try_fold(0_i128, |below, &(first, _)| first.checked_sub(below))
+        .ok_or(HistoryError::Overflow)
+}
+
+// A history as the unique polynomial through its readings, where the first reading is at x = 0.
+// It's kept in Newton's forward difference form, p(x) = sum over k of Δ^k y_0 * C(x, k), which
+// only needs the first entry of each row of the difference table.
+#[derive(Debug, PartialEq)]
+struct HistoryPolynomial {
+    // Δ^k y_0 for k up to the degree, so empty for the zero polynomial
+    leading_diffs: Vec<i128>,
+}
+
+impl HistoryPolynomial {
+    fn fit(readings: &[i128]) -> Result<HistoryPolynomial, HistoryError> {
+        let leading_diffs = difference_table_edges(readings)?
+            .iter()
+            .map(|&(first, _)| first)
+            .collect();
+        Ok(HistoryPolynomial { leading_diffs })
+    }
+
+    fn degree(&self) -> Option<usize> {
+        self.leading_diffs.len().checked_sub(1)
+    }
+
+    // Exact for any integer x, including negative ones: C(x, k) stays an integer, and each
+    // step of C(x, k + 1) = C(x, k) * (x - k) / (k + 1) divides exactly. Whatever part of k + 1
+    // C(x, k) doesn't cover must divide x - k, so we split the division between the two before
+    // multiplying, and only overflow if C(x, k + 1) itself doesn't fit.
+    fn evaluate(&self, x: i64) -> Result<i128, HistoryError> {
+        let mut binomial: i128 = 1;
+        let mut value: i128 = 0;
+        for (k, &diff) in self.leading_diffs.iter().enumerate() {
+            value = diff
+                .checked_mul(binomial)
+                .and_then(|term| value.checked_add(term))
+                .ok_or(HistoryError::Overflow)?;
+            if k + 1 == self.leading_diffs.len() {
+                break;
+            }
+            // a divisor of k + 1, so it fits
+            let divisor = gcd(binomial, k as i128 + 1) as i128;
+            binomial = (binomial / divisor)
+                .checked_mul((x as i128 - k as i128) / ((k as i128 + 1) / divisor))
+                .ok_or(HistoryError::Overflow)?;
+        }
+        Ok(value)
+    }
+
+    // Coefficients in the usual x^0, x^1, ... basis. C(x, k) = x(x - 1)...(x - k + 1) / k!, so
+    // we expand each falling factorial and divide through by k!. Both grow about as fast as k!,
+    // so by degree 34 or so they no longer fit in 128 bits.
+    fn coefficients(&self) -> Result<Vec<Rational>, HistoryError> {
+        let mut coefficients = vec![Rational::from(0); self.leading_diffs.len()];
+        // the falling factorial x(x - 1)...(x - k + 1), lowest power first
+        let mut falling_factorial: Vec<i128> = vec![1];
+        let mut factorial: i128 = 1;
+        for (k, &diff) in self.leading_diffs.iter().enumerate() {
+            for (power, &coefficient) in falling_factorial.iter().enumerate() {
+                coefficients[power] = diff
+                    .checked_mul(coefficient)
+                    .and_then(|term| Rational::from(term).divided_by(factorial))
+                    .and_then(|term| coefficients[power].checked_add(term))
+                    .ok_or(HistoryError::Overflow)?;
+            }
+            if k + 1 == self.leading_diffs.len() {
+                break;
+            }
+            // multiply by (x - k)
+            let mut next = vec![0; falling_factorial.len() + 1];
+            for (power, &coefficient) in falling_factorial.iter().enumerate() {
+                next[power + 1] = coefficient;
+            }
+            for (power, &coefficient) in falling_factorial.iter().enumerate() {
+                next[power] = coefficient
+                    .checked_mul(k as i128)
+                    .and_then(|product| next[power].checked_sub(product))
+                    .ok_or(HistoryError::Overflow)?;
+            }
+            falling_factorial = next;
+            factorial = factorial
+                .checked_mul(k as i128 + 1)
+                .ok_or(HistoryError::Overflow)?;
+        }
+        Ok(coefficients)
+    }
+
+    // Writes the polynomial out like "(1/2)x^2 + (3/2)x + 1".
+    fn closed_form(&self) -> Result<String, HistoryError> {
+        let mut terms: Vec<String> = Vec::new();
+        for (power, coefficient) in self.coefficients()?.iter().enumerate().rev() {
+            if coefficient.is_zero() {
+                continue;
+            }
+            let sign = match (terms.is_empty(), coefficient.is_negative()) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + ",
+            };
+            let text = coefficient.to_string();
+            let magnitude = match (text.trim_start_matches('-'), power) {
+                ("1", 1..) => String::new(),
+                (text, 1..) if text.contains('/') => format!("({text})"),
+                (text, _) => text.to_string(),
+            };
+            let variable = match power {
+                0 => String::new(),
+                1 => String::from("x"),
+                _ => format!("x^{power}"),
+            };
+            terms.push(format!("{sign}{magnitude}{variable}"));
+        }
+        if terms.is_empty() {
+            Ok(String::from("0"))
+        } else {
+            Ok(terms.concat())
+        }
+    }
+}
+
+fn read_histories(file_path: &str) -> Vec<Vec<i128>> {
+    let file = File::open(file_path).unwrap();
     let reader = BufReader::new(file);
-    let mut histories: Vec<Vec<i64>> = Vec::new();
+    let mut histories: Vec<Vec<i128>> = Vec::new();
     for line in reader.lines() {
         let line_content = &line.unwrap();
         let history = line_content
             .split_whitespace()
-            .map(|s| s.parse::<i64>().unwrap())
+            .map(|s| s.parse::<i128>().unwrap())
             .collect::<Vec<_>>();
         histories.push(history);
     }
+    histories
+}
 
-    let extrapolations = histories
-        .iter()
-        .map(|h| infer_last_reading(h))
-        .collect::<Vec<_>>();
-    let part_1_solution = extrapolations.iter().sum::<i64>();
-    println!("Part 1 solution: {part_1_solution}");
+// Sums what `infer` makes of each history, or says which history (counting from one) it failed
+// on and why.
+fn solve(
+    histories: &[Vec<i128>],
+    infer: impl Fn(&[i128]) -> Result<i128, HistoryError>,
+) -> Result<i128, (usize, HistoryError)> {
+    let mut sum: i128 = 0;
+    for (i, history) in histories.iter().enumerate() {
+        sum = infer(history)
+            .and_then(|value| sum.checked_add(value).ok_or(HistoryError::Overflow))
+            .map_err(|err| (i + 1, err))?;
+    }
+    Ok(sum)
+}
 
-    let first_reading_extrapolations = histories
-        .iter()
-        .map(|h| infer_first_reading(h))
-        .collect::<Vec<_>>();
-    let part_2_solution = first_reading_extrapolations.iter().sum::<i64>();
-    println!("Part 2 solution: {part_2_solution}");
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let histories = read_histories("resources/input_1");
+
+    // `--polynomials` prints the closed form of each history's polynomial
+    if args.len() > 1 && args[1] == "--polynomials" {
+        for (i, history) in histories.iter().enumerate() {
+            let polynomial = HistoryPolynomial::fit(history);
+            match polynomial
+                .and_then(|polynomial| Ok((polynomial.degree(), polynomial.closed_form()?)))
+            {
+                Ok((Some(degree), closed_form)) => {
+                    println!("degree {degree}: p(x) = {closed_form}")
+                }
+                Ok((None, _)) => println!("zero: p(x) = 0"),
+                Err(err) => println!("History {}: {err}", i + 1),
+            }
+        }
+        return;
+    }
+    // `--extrapolate <k>` sums the readings k steps after the last one and k steps before the
+    // first one
+    if args.len() > 2 && args[1] == "--extrapolate" {
+        let k = args[2].parse::<i64>().unwrap();
+        let forward_sum = solve(&histories, |history| {
+            HistoryPolynomial::fit(history)?.evaluate(history.len() as i64 - 1 + k)
+        });
+        let backward_sum = solve(&histories, |history| {
+            HistoryPolynomial::fit(history)?.evaluate(-k)
+        });
+        for (direction, sum) in [("forward", forward_sum), ("backward", backward_sum)] {
+            match sum {
+                Ok(sum) => println!("Sum of readings {k} steps {direction}: {sum}"),
+                Err((line, err)) => println!("History {line}: {err}"),
+            }
+        }
+        return;
+    }
+
+    match solve(&histories, infer_last_reading) {
+        Ok(part_1_solution) => println!("Part 1 solution: {part_1_solution}"),
+        Err((line, err)) => println!("Part 1 error in history {line}: {err}"),
+    }
+
+    match solve(&histories, infer_first_reading) {
+        Ok(part_2_solution) => println!("Part 2 solution: {part_2_solution}"),
+        Err((line, err)) => println!("Part 2 error in history {line}: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polynomial_matches_one_step_extrapolation() {
+        for file_path in ["resources/sample_1", "resources/input_1"] {
+            for history in read_histories(file_path) {
+                let polynomial = HistoryPolynomial::fit(&history).unwrap();
+                for (x, &reading) in history.iter().enumerate() {
+                    assert_eq!(polynomial.evaluate(x as i64), Ok(reading));
+                }
+                assert_eq!(
+                    polynomial.evaluate(history.len() as i64),
+                    infer_last_reading(&history)
+                );
+                assert_eq!(polynomial.evaluate(-1), infer_first_reading(&history));
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_form() {
+        let closed_forms = read_histories("resources/sample_1")
+            .iter()
+            .map(|history| {
+                HistoryPolynomial::fit(history)
+                    .unwrap()
+                    .closed_form()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            closed_forms,
+            [
+                "3x",
+                "(1/2)x^2 + (3/2)x + 1",
+                "(1/3)x^3 - x^2 + (11/3)x + 10"
+            ]
+        );
+        assert_eq!(
+            HistoryPolynomial::fit(&[0, 0, 0])
+                .unwrap()
+                .closed_form()
+                .unwrap(),
+            "0"
+        );
+        assert_eq!(HistoryPolynomial::fit(&[0, 0, 0]).unwrap().degree(), None);
+        assert_eq!(
+            HistoryPolynomial::fit(&[-2, -2])
+                .unwrap()
+                .closed_form()
+                .unwrap(),
+            "-2"
+        );
+        assert_eq!(
+            HistoryPolynomial::fit(&[5, 4, 1, -4])
+                .unwrap()
+                .closed_form()
+                .unwrap(),
+            "-x^2 + 5"
+        );
+    }
+
+    // p(x) mod 2^61 - 1, which is prime, worked out independently of `evaluate` with C(x, k)
+    // built up modulo the prime so nothing can overflow.
+    fn evaluate_mod_prime(polynomial: &HistoryPolynomial, x: i64) -> u128 {
+        const PRIME: u128 = (1 << 61) - 1;
+        let to_field = |n: i128| n.rem_euclid(PRIME as i128) as u128;
+        let inverse = |n: u128| {
+            // Fermat's little theorem: n^(p - 2) is n's inverse
+            let (mut result, mut base, mut exponent) = (1, n, PRIME - 2);
+            while exponent > 0 {
+                if exponent & 1 == 1 {
+                    result = result * base % PRIME;
+                }
+                base = base * base % PRIME;
+                exponent >>= 1;
+            }
+            result
+        };
+        let (mut binomial, mut value) = (1, 0);
+        for (k, &diff) in polynomial.leading_diffs.iter().enumerate() {
+            value = (value + to_field(diff) * binomial) % PRIME;
+            binomial = binomial * to_field(x as i128 - k as i128) % PRIME;
+            binomial = binomial * inverse(k as u128 + 1) % PRIME;
+        }
+        value
+    }
+
+    #[test]
+    fn test_far_extrapolation() {
+        // 1,000 steps out, most histories reach values of around 10^37, which is close to the
+        // limit but fits. A few go past 128 bits, in the answer or in one of its terms.
+        let mut num_evaluated = 0;
+        for history in read_histories("resources/input_1") {
+            let polynomial = HistoryPolynomial::fit(&history).unwrap();
+            for x in [history.len() as i64 - 1 + 1_000, -1_000] {
+                if let Ok(value) = polynomial.evaluate(x) {
+                    assert_eq!(
+                        value.rem_euclid((1 << 61) - 1) as u128,
+                        evaluate_mod_prime(&polynomial, x)
+                    );
+                    num_evaluated += 1;
+                }
+            }
+        }
+        assert_eq!(num_evaluated, 400 - 20);
+        let first_history = &read_histories("resources/input_1")[0];
+        let value = HistoryPolynomial::fit(first_history)
+            .unwrap()
+            .evaluate(first_history.len() as i64 - 1 + 1_000)
+            .unwrap();
+        assert!(value < -10_i128.pow(37));
+
+        // but an x big enough to push a term past 128 bits is reported
+        assert_eq!(
+            HistoryPolynomial::fit(&[0, 0, 1, 3])
+                .unwrap()
+                .evaluate(i64::MAX),
+            Ok(((i64::MAX as i128) * (i64::MAX as i128 - 1)) / 2)
+        );
+        assert_eq!(
+            HistoryPolynomial::fit(&[0, 0, 0, 1, 4])
+                .unwrap()
+                .evaluate(i64::MAX),
+            Err(HistoryError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_high_degree_closed_form() {
+        // readings of C(x, k) for x = 0..=k + 1, which fit a degree k polynomial
+        let binomial_readings = |k: i128| {
+            (0..=k + 1)
+                .map(|x| (0..k).fold(1, |binomial, i| binomial * (x - i) / (i + 1)))
+                .collect::<Vec<i128>>()
+        };
+
+        let polynomial = HistoryPolynomial::fit(&binomial_readings(20)).unwrap();
+        assert_eq!(polynomial.degree(), Some(20));
+        let closed_form = polynomial.closed_form().unwrap();
+        // x(x - 1)...(x - 19) / 20!
+        assert!(closed_form.starts_with("(1/2432902008176640000)x^20 - (1/12804747411456000)x^19"));
+        assert!(closed_form.ends_with(" - (1/20)x"));
+
+        // by degree 34, 34! alone is too big for 128 bits, so there's no closed form to give,
+        // though the polynomial can still be evaluated
+        let polynomial = HistoryPolynomial::fit(&binomial_readings(34)).unwrap();
+        assert_eq!(polynomial.degree(), Some(34));
+        assert_eq!(polynomial.closed_form(), Err(HistoryError::Overflow));
+        assert_eq!(polynomial.evaluate(36), Ok(36 * 35 / 2));
+    }
+
+    #[test]
+    fn test_k_step_extrapolation() {
+        // 1, 3, 6, 10, ... are the triangular numbers (x + 1)(x + 2) / 2
+        let polynomial = HistoryPolynomial::fit(&[1, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(polynomial.degree(), Some(2));
+        for x in -50..50 {
+            assert_eq!(
+                polynomial.evaluate(x),
+                Ok((x as i128 + 1) * (x as i128 + 2) / 2)
+            );
+        }
+
+        // a cubic evaluated far outside the readings agrees with repeated one-step extrapolation
+        let mut readings = vec![10, 13, 16, 21, 30, 45];
+        let polynomial = HistoryPolynomial::fit(&readings).unwrap();
+        for _ in 0..20 {
+            readings.push(infer_last_reading(&readings).unwrap());
+        }
+        assert_eq!(polynomial.evaluate(25), Ok(*readings.last().unwrap()));
+        let mut reversed = vec![10, 13, 16, 21, 30, 45];
+        reversed.reverse();
+        for _ in 0..20 {
+            reversed.push(infer_last_reading(&reversed).unwrap());
+        }
+        assert_eq!(polynomial.evaluate(-20), Ok(*reversed.last().unwrap()));
+    }
+
+    #[test]
+    fn test_part_solutions() {
+        let histories = read_histories("resources/sample_1");
+        assert_eq!(solve(&histories, infer_last_reading), Ok(114));
+        assert_eq!(solve(&histories, infer_first_reading), Ok(2));
+        let histories = read_histories("resources/input_1");
+        assert_eq!(solve(&histories, infer_last_reading), Ok(1877825184));
+        assert_eq!(solve(&histories, infer_first_reading), Ok(1108));
+    }
+
+    #[test]
+    fn test_history_errors() {
+        assert_eq!(infer_last_reading(&[]), Err(HistoryError::Empty));
+        assert_eq!(
+            infer_last_reading(&[7]),
+            Err(HistoryError::DoesNotConverge { degree_reached: 0 })
+        );
+        // powers of two are their own differences, so every row is nonzero
+        let err = infer_first_reading(&[1, 2, 4, 8, 16]).unwrap_err();
+        assert_eq!(err, HistoryError::DoesNotConverge { degree_reached: 4 });
+        assert_eq!(
+            err.to_string(),
+            "The differences never reach a row of zeros: the table ran out of readings at degree 4"
+        );
+        assert_eq!(
+            HistoryPolynomial::fit(&[3, 1, 4, 1, 5]),
+            Err(HistoryError::DoesNotConverge { degree_reached: 4 })
+        );
+
+        // a repeated value still converges
+        assert_eq!(infer_last_reading(&[7, 7]), Ok(7));
+        assert_eq!(infer_last_reading(&[0]), Ok(0));
+
+        // values well beyond i64 are fine, and overflowing i128 is reported
+        let big = i64::MAX as i128 * 1000;
+        assert_eq!(infer_last_reading(&[big, 2 * big, 3 * big]), Ok(4 * big));
+        assert_eq!(
+            infer_last_reading(&[i128::MIN, i128::MAX]),
+            Err(HistoryError::Overflow)
+        );
+        assert_eq!(
+            infer_last_reading(&[i128::MAX - 2, i128::MAX - 1, i128::MAX]),
+            Err(HistoryError::Overflow)
+        );
+        let polynomial = HistoryPolynomial::fit(&[0, 1, 4, 9]).unwrap();
+        assert_eq!(
+            polynomial.evaluate(i64::MAX),
+            Ok(i64::MAX as i128 * i64::MAX as i128)
+        );
+        let polynomial = HistoryPolynomial::fit(&[0, 1, 8, 27, 64]).unwrap();
+        assert_eq!(polynomial.evaluate(i64::MAX), Err(HistoryError::Overflow));
+
+        let histories = vec![vec![1, 2, 3], vec![1, 2, 4]];
+        assert_eq!(
+            solve(&histories, infer_last_reading),
+            Err((2, HistoryError::DoesNotConverge { degree_reached: 2 }))
+        );
+    }
+
+    #[test]
+    fn test_long_history() {
+        // 100k readings of a cubic, which would have been 100k levels of recursion
+        let cubic = |x: i128| 2 * x * x * x - 7 * x * x + 3 * x - 11;
+        let num_readings = 100_000;
+        let readings = (0..num_readings).map(cubic).collect::<Vec<_>>();
+        assert_eq!(infer_last_reading(&readings), Ok(cubic(num_readings)));
+        assert_eq!(infer_first_reading(&readings), Ok(cubic(-1)));
+        let polynomial = HistoryPolynomial::fit(&readings).unwrap();
+        assert_eq!(polynomial.degree(), Some(3));
+        assert_eq!(polynomial.closed_form().unwrap(), "2x^3 - 7x^2 + 3x - 11");
+
+        let mut row = vec![1, 4, 9, 16];
+        generate_diffs_in_place(&mut row).unwrap();
+        assert_eq!(row[..3], [3, 5, 7]);
+    }
 }
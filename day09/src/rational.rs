@@ -0,0 +1,160 @@
+use std::fmt;
+
+// Works on the magnitudes, since the gcd of i128::MIN and 0 (or itself) is 2^127.
+pub fn gcd(a: i128, b: i128) -> u128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// `n` divided by one of its divisors from `gcd`. The only divisor too big for an i128 is 2^127,
+// which only divides 0 and i128::MIN.
+fn exact_div(n: i128, divisor: u128) -> i128 {
+    match i128::try_from(divisor) {
+        Ok(divisor) => n / divisor,
+        Err(_) => -(n / i128::MIN),
+    }
+}
+
+// An exact fraction, always kept in lowest terms with a positive denominator so that equal
+// values have equal fields.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub fn new(numerator: i128, denominator: i128) -> Rational {
+        Rational::checked_new(numerator, denominator).expect("Rational doesn't fit in an i128")
+    }
+
+    // None if the fraction in lowest terms doesn't fit, which can only happen when flipping the
+    // signs to make the denominator positive overflows.
+    pub fn checked_new(numerator: i128, denominator: i128) -> Option<Rational> {
+        assert!(denominator != 0, "Rational with a zero denominator");
+        let divisor = gcd(numerator, denominator);
+        let (numerator, denominator) = (
+            exact_div(numerator, divisor),
+            exact_div(denominator, divisor),
+        );
+        if denominator < 0 {
+            Some(Rational {
+                numerator: numerator.checked_neg()?,
+                denominator: denominator.checked_neg()?,
+            })
+        } else {
+            Some(Rational {
+                numerator,
+                denominator,
+            })
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numerator < 0
+    }
+
+    // None if the result doesn't fit.
+    pub fn divided_by(&self, divisor: i128) -> Option<Rational> {
+        // cancel what we can first so the denominator grows as little as possible
+        let common = gcd(self.numerator, divisor).max(1);
+        let denominator = self.denominator.checked_mul(exact_div(divisor, common))?;
+        Rational::checked_new(exact_div(self.numerator, common), denominator)
+    }
+
+    // None if the result doesn't fit.
+    pub fn checked_add(&self, other: Rational) -> Option<Rational> {
+        let common = gcd(self.denominator, other.denominator);
+        let (self_scale, other_scale) = (
+            exact_div(other.denominator, common),
+            exact_div(self.denominator, common),
+        );
+        let numerator = self
+            .numerator
+            .checked_mul(self_scale)?
+            .checked_add(other.numerator.checked_mul(other_scale)?)?;
+        let denominator = self.denominator.checked_mul(self_scale)?;
+        Rational::checked_new(numerator, denominator)
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(n: i128) -> Self {
+        Rational::new(n, 1)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rational() {
+        assert_eq!(Rational::new(6, -4), Rational::new(-3, 2));
+        assert_eq!(Rational::new(0, 5), Rational::from(0));
+        assert_eq!(
+            Rational::new(1, 2).checked_add(Rational::new(1, 3)),
+            Some(Rational::new(5, 6))
+        );
+        assert_eq!(Rational::new(3, 2).divided_by(3), Some(Rational::new(1, 2)));
+        assert_eq!(Rational::new(0, 1).divided_by(5), Some(Rational::from(0)));
+
+        // big values only fail once the result itself doesn't fit
+        let tiny = Rational::new(1, i128::MAX / 2);
+        assert_eq!(
+            tiny.checked_add(tiny),
+            Some(Rational::new(2, i128::MAX / 2))
+        );
+        assert_eq!(
+            Rational::new(i128::MAX / 4, 3).divided_by(i128::MAX / 4),
+            Some(Rational::new(1, 3))
+        );
+        assert_eq!(Rational::new(1, 3).divided_by(i128::MAX), None);
+        assert_eq!(
+            Rational::from(i128::MAX).checked_add(Rational::from(1)),
+            None
+        );
+        assert_eq!(Rational::new(-3, 2).to_string(), "-3/2");
+        assert_eq!(Rational::new(4, 2).to_string(), "2");
+        assert!(Rational::new(-1, 3).is_negative());
+
+        // i128::MIN's magnitude doesn't fit in an i128, but it still reduces
+        assert_eq!(gcd(i128::MIN, 0), 1 << 127);
+        assert_eq!(gcd(i128::MIN, i128::MIN), 1 << 127);
+        assert_eq!(gcd(i128::MIN, 6), 2);
+        assert_eq!(Rational::new(i128::MIN, i128::MIN), Rational::from(1));
+        assert_eq!(Rational::new(0, i128::MIN), Rational::from(0));
+        assert_eq!(
+            Rational::new(i128::MIN, 4).to_string(),
+            (i128::MIN / 4).to_string()
+        );
+        assert_eq!(Rational::checked_new(i128::MIN, -1), None);
+        assert_eq!(Rational::checked_new(1, i128::MIN), None);
+        assert_eq!(
+            Rational::from(i128::MIN).divided_by(i128::MIN),
+            Some(Rational::from(1))
+        );
+        assert_eq!(Rational::from(i128::MIN).divided_by(-1), None);
+        assert_eq!(
+            Rational::from(i128::MIN + 1).checked_add(Rational::from(-1)),
+            Some(Rational::from(i128::MIN))
+        );
+    }
+}
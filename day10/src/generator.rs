@@ -0,0 +1,254 @@
+use super::Tile;
+
+// xorshift, which is plenty random enough for making up mazes
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck on zero
+        Rng(seed.max(1))
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+pub struct GeneratedLoop {
+    // the maze in the puzzle's tile alphabet, one row per line
+    pub text: String,
+    pub loop_len: usize,
+    pub num_enclosed: usize,
+}
+
+// Makes a random blob of cells on a (width - 1) × (height - 1) board and draws its outline as the
+// loop. The outline runs along the corners between cells, which are the width × height tiles, and
+// a tile is enclosed exactly when all four cells around it are in the blob.
+//
+// The blob is grown one neighbour at a time up to a random size, so it's in one piece. Then any
+// holes are filled in, and so is anywhere it only touches itself diagonally, so that its outline
+// is a single simple loop. S goes somewhere random on the loop. With `decoys`, about half of the
+// ground gets random pipes that aren't part of the loop, though never right next to S so that
+// what S is stays unambiguous.
+pub fn generate_loop(rng: &mut Rng, width: usize, height: usize, decoys: bool) -> GeneratedLoop {
+    assert!(
+        width >= 2 && height >= 2,
+        "The smallest loop takes up 2 × 2 tiles"
+    );
+    let (cells_width, cells_height) = (width - 1, height - 1);
+    let mut cells = vec![vec![false; cells_width]; cells_height];
+    let start = (rng.below(cells_width), rng.below(cells_height));
+    cells[start.1][start.0] = true;
+    let mut num_cells = 1;
+    // cells of the blob that might still have empty neighbours, newest last
+    let mut growable = vec![start];
+    let target_size = 1 + rng.below(cells_width * cells_height);
+    while num_cells < target_size {
+        // mostly grow from the newest cells, which makes for twistier loops
+        let i = if rng.below(4) == 0 {
+            rng.below(growable.len())
+        } else {
+            growable.len() - 1 - rng.below(growable.len().min(3))
+        };
+        let (x, y) = growable[i];
+        let empty_neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ]
+        .into_iter()
+        .filter(|&(nx, ny)| nx < cells_width && ny < cells_height && !cells[ny][nx])
+        .collect::<Vec<_>>();
+        if empty_neighbours.is_empty() {
+            growable.swap_remove(i);
+            continue;
+        }
+        let (nx, ny) = empty_neighbours[rng.below(empty_neighbours.len())];
+        cells[ny][nx] = true;
+        num_cells += 1;
+        growable.push((nx, ny));
+    }
+    loop {
+        fill_holes(&mut cells);
+        if !fill_pinches(&mut cells) {
+            break;
+        }
+    }
+
+    let cell = |x: usize, y: usize| {
+        x > 0 && y > 0 && x <= cells_width && y <= cells_height && cells[y - 1][x - 1]
+    };
+    let mut grid = vec![vec![Tile::Ground; width]; height];
+    let mut loop_coords = Vec::new();
+    let mut num_enclosed = 0;
+    for (y, row) in grid.iter_mut().enumerate() {
+        for (x, tile) in row.iter_mut().enumerate() {
+            // the cells to the north west, north east, south west and south east of the tile
+            let (nw, ne, sw, se) = (
+                cell(x, y),
+                cell(x + 1, y),
+                cell(x, y + 1),
+                cell(x + 1, y + 1),
+            );
+            // the outline runs between cells where one is in the blob and the other isn't
+            *tile = match (nw != ne, sw != se, ne != se, nw != sw) {
+                (true, true, false, false) => Tile::VerticalPipe,
+                (false, false, true, true) => Tile::HorizontalPipe,
+                (true, false, true, false) => Tile::NorthEastPipe,
+                (true, false, false, true) => Tile::NorthWestPipe,
+                (false, true, true, false) => Tile::SouthEastPipe,
+                (false, true, false, true) => Tile::SouthWestPipe,
+                _ => {
+                    if nw && ne && sw && se {
+                        num_enclosed += 1;
+                    }
+                    continue;
+                }
+            };
+            loop_coords.push((x, y));
+        }
+    }
+
+    let (start_x, start_y) = loop_coords[rng.below(loop_coords.len())];
+    grid[start_y][start_x] = Tile::StartPipe;
+    if decoys {
+        for (y, row) in grid.iter_mut().enumerate() {
+            for (x, tile) in row.iter_mut().enumerate() {
+                if *tile == Tile::Ground
+                    && x.abs_diff(start_x) + y.abs_diff(start_y) > 1
+                    && rng.below(2) == 0
+                {
+                    *tile = Tile::PIPES[rng.below(Tile::PIPES.len())];
+                }
+            }
+        }
+    }
+
+    let text = grid
+        .iter()
+        .map(|row| row.iter().map(|tile| tile.to_char()).collect::<String>() + "\n")
+        .collect::<String>();
+    GeneratedLoop {
+        text,
+        loop_len: loop_coords.len(),
+        num_enclosed,
+    }
+}
+
+// Fills in every empty cell that can't reach the edge of the board.
+fn fill_holes(cells: &mut [Vec<bool>]) {
+    let (width, height) = (cells[0].len(), cells.len());
+    let mut reaches_edge = vec![vec![false; width]; height];
+    let mut to_visit = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| x == 0 || y == 0 || x + 1 == width || y + 1 == height)
+        .collect::<Vec<_>>();
+    while let Some((x, y)) = to_visit.pop() {
+        if cells[y][x] || reaches_edge[y][x] {
+            continue;
+        }
+        reaches_edge[y][x] = true;
+        to_visit.extend(
+            [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ]
+            .into_iter()
+            .filter(|&(nx, ny)| nx < width && ny < height),
+        );
+    }
+    for (row, reaches_edge_row) in cells.iter_mut().zip(reaches_edge) {
+        for (cell, reaches_edge) in row.iter_mut().zip(reaches_edge_row) {
+            *cell |= !reaches_edge;
+        }
+    }
+}
+
+// Fills in one of the empty cells wherever two cells of the blob only touch at a corner, which
+// would have the outline cross itself there. Returns whether anything was filled.
+fn fill_pinches(cells: &mut [Vec<bool>]) -> bool {
+    let mut filled_any = false;
+    for y in 1..cells.len() {
+        for x in 1..cells[0].len() {
+            let (nw, ne, sw, se) = (
+                cells[y - 1][x - 1],
+                cells[y - 1][x],
+                cells[y][x - 1],
+                cells[y][x],
+            );
+            if nw == se && ne == sw && nw != ne {
+                // fill in whichever empty cell is on the northern row
+                if nw {
+                    cells[y - 1][x] = true;
+                } else {
+                    cells[y - 1][x - 1] = true;
+                }
+                filled_any = true;
+            }
+        }
+    }
+    filled_any
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        count_enclosed, count_enclosed_by_pick, count_enclosed_by_scanline, find_main_loop,
+        find_start, main_loop_grid, parse_grid, squeeze_flood_fill,
+    };
+
+    fn check(generated: &GeneratedLoop) {
+        let grid = parse_grid(&generated.text);
+        let main_loop = find_main_loop(&grid, find_start(&grid).unwrap()).unwrap();
+        assert_eq!(
+            main_loop.vertices.len(),
+            generated.loop_len,
+            "{}",
+            generated.text
+        );
+        let loop_grid = main_loop_grid(&grid, &main_loop);
+        let num_enclosed = generated.num_enclosed;
+        assert_eq!(
+            count_enclosed(&squeeze_flood_fill(&loop_grid)),
+            num_enclosed,
+            "{}",
+            generated.text
+        );
+        assert_eq!(count_enclosed_by_pick(&main_loop.vertices), num_enclosed);
+        assert_eq!(count_enclosed_by_scanline(&loop_grid), num_enclosed);
+    }
+
+    #[test]
+    fn test_smallest_loop() {
+        let generated = generate_loop(&mut Rng::new(7), 2, 2, true);
+        assert_eq!(generated.loop_len, 4);
+        assert_eq!(generated.num_enclosed, 0);
+        // S could be any corner of the square
+        assert_eq!(generated.text.len(), 6);
+        assert_eq!(generated.text.matches(['S', 'F', '7', 'L', 'J']).count(), 4);
+    }
+
+    #[test]
+    fn test_small_loops() {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        for i in 0..300 {
+            let (width, height) = (2 + rng.below(12), 2 + rng.below(12));
+            check(&generate_loop(&mut rng, width, height, i % 2 == 0));
+        }
+    }
+
+    #[test]
+    fn test_puzzle_sized_loop() {
+        let mut rng = Rng::new(10);
+        let generated = generate_loop(&mut rng, 140, 140, true);
+        assert!(generated.num_enclosed > 0);
+        check(&generated);
+    }
+}
@@ -1,6 +1,13 @@
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::env;
+use std::fmt;
+use std::fs;
+
+mod generator;
+mod render;
+
+use generator::{generate_loop, Rng};
+use render::{display_grid_with_known_accessibilities, render_svg, render_unicode};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum TileAccessibility {
@@ -9,12 +16,7 @@ enum TileAccessibility {
     MainLoopPipe,
 }
 
-#[derive(Debug)]
-struct SearchParams {
-    route: Vec<Coords>,
-}
-
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Direction {
     North,
     South,
@@ -22,6 +24,57 @@ enum Direction {
     West,
 }
 
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::South,
+        Direction::East,
+        Direction::West,
+    ];
+
+    fn opposite(self) -> Direction {
+        use Direction::*;
+        match self {
+            North => South,
+            South => North,
+            East => West,
+            West => East,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Orientation {
+    Clockwise,
+    CounterClockwise,
+}
+
+#[derive(Debug)]
+struct PipeLoop {
+    // in walking order, starting from S for the main loop
+    vertices: Vec<Coords>,
+    orientation: Orientation,
+    // what the first tile really is, which matters when it's S
+    start_tile: Tile,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LoopError {
+    NoLoopThroughStart { start: Coords },
+}
+
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoopError::NoLoopThroughStart { start } => write!(
+                f,
+                "none of the ways S at ({}, {}) could connect lead back round to it",
+                start.x, start.y
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 struct Coords {
     x: usize,
@@ -49,6 +102,15 @@ enum Tile {
 }
 
 impl Tile {
+    const PIPES: [Tile; 6] = [
+        Tile::VerticalPipe,
+        Tile::HorizontalPipe,
+        Tile::NorthEastPipe,
+        Tile::NorthWestPipe,
+        Tile::SouthEastPipe,
+        Tile::SouthWestPipe,
+    ];
+
     fn from_char(c: char) -> Option<Tile> {
         use Tile::*;
         match c {
@@ -64,7 +126,7 @@ impl Tile {
         }
     }
 
-    fn to_char(&self) -> char {
+    fn to_char(self) -> char {
         use Tile::*;
         match self {
             Ground => '.',
@@ -80,74 +142,56 @@ impl Tile {
 
     fn connects_south(&self) -> bool {
         use Tile::*;
-        match self {
-            StartPipe => true,
-            VerticalPipe => true,
-            SouthEastPipe => true,
-            SouthWestPipe => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            StartPipe | VerticalPipe | SouthEastPipe | SouthWestPipe
+        )
     }
 
     fn connects_north(&self) -> bool {
         use Tile::*;
-        match self {
-            StartPipe => true,
-            VerticalPipe => true,
-            NorthEastPipe => true,
-            NorthWestPipe => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            StartPipe | VerticalPipe | NorthEastPipe | NorthWestPipe
+        )
     }
 
     fn connects_east(&self) -> bool {
         use Tile::*;
-        match self {
-            StartPipe => true,
-            HorizontalPipe => true,
-            NorthEastPipe => true,
-            SouthEastPipe => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            StartPipe | HorizontalPipe | NorthEastPipe | SouthEastPipe
+        )
     }
 
     fn connects_west(&self) -> bool {
         use Tile::*;
-        match self {
-            StartPipe => true,
-            HorizontalPipe => true,
-            NorthWestPipe => true,
-            SouthWestPipe => true,
-            _ => false,
+        matches!(
+            self,
+            StartPipe | HorizontalPipe | NorthWestPipe | SouthWestPipe
+        )
+    }
+
+    fn connects(&self, direction: Direction) -> bool {
+        match direction {
+            Direction::North => self.connects_north(),
+            Direction::South => self.connects_south(),
+            Direction::East => self.connects_east(),
+            Direction::West => self.connects_west(),
         }
     }
 }
 
-fn display_grid_with_known_accessibilities(
-    grid: &Vec<Vec<Tile>>,
-    accessibilities: &Vec<Vec<Option<TileAccessibility>>>,
-) -> String {
-    let mut printable: String = String::new();
-    for (y, row) in grid.iter().enumerate() {
-        let row_str = row
-            .iter()
-            .enumerate()
-            .map(|(x, tile)| {
-                if *tile == Tile::Ground {
-                    let maybe_accessibility = accessibilities[y][x];
-                    match maybe_accessibility {
-                        Some(TileAccessibility::UnenclosedGround) => 'O',
-                        Some(TileAccessibility::MainLoopEnclosedGround) => 'I',
-                        _ => tile.to_char(),
-                    }
-                } else {
-                    tile.to_char()
-                }
-            })
-            .collect::<String>();
-        printable = format!("{}{}\n", printable, row_str);
-    }
-    format!("{}\n", printable)
+// The coords one step away in the given direction, if they're still on the grid.
+fn neighbour(grid: &[Vec<Tile>], coords: Coords, direction: Direction) -> Option<Coords> {
+    let Coords { x, y } = coords;
+    let (x, y) = match direction {
+        Direction::North => (x, y.checked_sub(1)?),
+        Direction::South => (x, y + 1),
+        Direction::East => (x + 1, y),
+        Direction::West => (x.checked_sub(1)?, y),
+    };
+    (y < grid.len() && x < grid[y].len()).then_some(Coords { x, y })
 }
 
 fn allows_passage_between(
@@ -231,499 +275,804 @@ fn pipe_pair_allows_passage_east_or_west(
     }
 }
 
-fn main() {
-    let file = File::open("resources/input_1").unwrap();
-    let reader = BufReader::new(file);
-    let mut grid: Vec<Vec<Tile>> = Vec::new();
-    let mut visited: Vec<Vec<bool>> = Vec::new();
-    let mut accessibilities: Vec<Vec<Option<TileAccessibility>>> = Vec::new();
-    for line in reader.lines() {
-        let line_content = &line.unwrap();
-        let row = line_content
-            .chars()
-            .map(|c| Tile::from_char(c).unwrap())
-            .collect::<Vec<_>>();
-        let row_len = row.len();
-        grid.push(row);
-
-        let visited_row = vec![false; row_len];
-        visited.push(visited_row);
-
-        let accessibilities_row = vec![None; row_len];
-        accessibilities.push(accessibilities_row);
-    }
+fn parse_grid(file_content: &str) -> Vec<Vec<Tile>> {
+    file_content
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| Tile::from_char(c).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
 
-    // find starting tile
-    let mut maybe_starting_tile_coords = None;
+// Where S is, if the grid has one at all.
+fn find_start(grid: &[Vec<Tile>]) -> Option<Coords> {
     for (y, row) in grid.iter().enumerate() {
         for (x, tile) in row.iter().enumerate() {
             if *tile == Tile::StartPipe {
-                maybe_starting_tile_coords = Some(Coords { x, y });
-                break;
+                return Some(Coords { x, y });
             }
         }
     }
+    None
+}
 
-    let grid_max_col_index = grid.len() - 1;
-    // assuming all rows are equal length
-    let grid_max_row_index = grid[0].len() - 1;
-
-    let mut solution_route: Option<Vec<Coords>> = None;
+// Follows the pipes round from `start`, treating it as `start_tile`, leaving every pipe by
+// whichever connection we didn't come in through, so all we need to remember between steps is
+// the way we came. If the pipes lead off the grid, or into a tile that doesn't connect back, we
+// get the tiles we made it through instead.
+fn walk_loop(grid: &[Vec<Tile>], start: Coords, start_tile: Tile) -> Result<PipeLoop, Vec<Coords>> {
+    let mut heading = *Direction::ALL
+        .iter()
+        .find(|&&direction| start_tile.connects(direction))
+        .unwrap();
+    let mut vertices = vec![start];
+    let mut curr_coords = start;
+    loop {
+        curr_coords = match neighbour(grid, curr_coords, heading) {
+            Some(next_coords) => next_coords,
+            None => return Err(vertices),
+        };
+        if curr_coords == start {
+            break;
+        }
+        let curr_tile = grid[curr_coords.y][curr_coords.x];
+        if curr_tile == Tile::StartPipe || !curr_tile.connects(heading.opposite()) {
+            return Err(vertices);
+        }
+        vertices.push(curr_coords);
+        heading = *Direction::ALL
+            .iter()
+            .find(|&&direction| direction != heading.opposite() && curr_tile.connects(direction))
+            .unwrap();
+    }
+    if !start_tile.connects(heading.opposite()) {
+        return Err(vertices);
+    }
 
-    let starting_tile_coords = maybe_starting_tile_coords.unwrap();
-    let mut search_stack: Vec<SearchParams> = vec![SearchParams {
-        route: vec![starting_tile_coords],
-    }];
+    // y grows southwards, so a positive area means the loop turns clockwise on screen
+    let orientation = if twice_signed_area(&vertices) > 0 {
+        Orientation::Clockwise
+    } else {
+        Orientation::CounterClockwise
+    };
+    Ok(PipeLoop {
+        vertices,
+        orientation,
+        start_tile,
+    })
+}
 
-    while let Some(search_params) = search_stack.pop() {
-        let curr_coords = search_params.route[search_params.route.len() - 1];
-        visited[curr_coords.y][curr_coords.x] = true;
+// The loop through S, trying each pipe S could be in turn until one leads back round to it. If
+// more than one does, the first in `Tile::PIPES` order wins.
+fn find_main_loop(grid: &[Vec<Tile>], starting_tile_coords: Coords) -> Result<PipeLoop, LoopError> {
+    start_tile_candidates(grid, starting_tile_coords)
+        .into_iter()
+        .find_map(|start_tile| walk_loop(grid, starting_tile_coords, start_tile).ok())
+        .ok_or(LoopError::NoLoopThroughStart {
+            start: starting_tile_coords,
+        })
+}
 
-        // search in every possible direction
-        let curr_tile = grid[curr_coords.y][curr_coords.x];
-        if curr_tile.connects_north()
-            && curr_coords.y > 0
-            && grid[curr_coords.y - 1][curr_coords.x].connects_south()
-        {
-            if search_params.route.len() > 2
-                && grid[curr_coords.y - 1][curr_coords.x] == Tile::StartPipe
-            {
-                solution_route = Some(search_params.route);
-                break;
-            } else if !visited[curr_coords.y - 1][curr_coords.x] {
-                let mut new_route = search_params.route.clone();
-                new_route.push(Coords {
-                    y: curr_coords.y - 1,
-                    x: curr_coords.x,
-                });
-                search_stack.push(SearchParams { route: new_route });
-            }
-        }
+// Every pipe S could be, going by which of its neighbours connect to it. There's usually just
+// the one, but S can have more than two neighbours pointing at it.
+fn start_tile_candidates(grid: &[Vec<Tile>], starting_tile_coords: Coords) -> Vec<Tile> {
+    let connects_back = |direction: Direction| {
+        neighbour(grid, starting_tile_coords, direction)
+            .is_some_and(|coords| grid[coords.y][coords.x].connects(direction.opposite()))
+    };
+    Tile::PIPES
+        .into_iter()
+        .filter(|tile| {
+            Direction::ALL
+                .iter()
+                .all(|&direction| !tile.connects(direction) || connects_back(direction))
+        })
+        .collect()
+}
 
-        if curr_tile.connects_south()
-            && curr_coords.y < grid_max_col_index
-            && grid[curr_coords.y + 1][curr_coords.x].connects_north()
-        {
-            if search_params.route.len() > 2
-                && grid[curr_coords.y + 1][curr_coords.x] == Tile::StartPipe
-            {
-                solution_route = Some(search_params.route);
-                break;
-            } else if !visited[curr_coords.y + 1][curr_coords.x] {
-                let mut new_route = search_params.route.clone();
-                new_route.push(Coords {
-                    y: curr_coords.y + 1,
-                    x: curr_coords.x,
-                });
-                search_stack.push(SearchParams { route: new_route });
-            }
-        }
+// Every closed loop of pipes in the grid, ordered by where they first turn up reading row by
+// row. S counts as whichever pipe puts it on a loop, or as ground if none does, and a grid
+// without an S is taken as it is.
+fn find_all_loops(grid: &[Vec<Tile>]) -> Vec<PipeLoop> {
+    let mut grid = grid.to_vec();
+    if let Some(starting_tile_coords) = find_start(&grid) {
+        grid[starting_tile_coords.y][starting_tile_coords.x] =
+            match find_main_loop(&grid, starting_tile_coords) {
+                Ok(main_loop) => main_loop.start_tile,
+                Err(_) => Tile::Ground,
+            };
+    }
 
-        if curr_tile.connects_west()
-            && curr_coords.x > 0
-            && grid[curr_coords.y][curr_coords.x - 1].connects_east()
-        {
-            if search_params.route.len() > 2
-                && grid[curr_coords.y][curr_coords.x - 1] == Tile::StartPipe
-            {
-                solution_route = Some(search_params.route);
-                break;
-            } else if !visited[curr_coords.y][curr_coords.x - 1] {
-                let mut new_route = search_params.route.clone();
-                new_route.push(Coords {
-                    y: curr_coords.y,
-                    x: curr_coords.x - 1,
-                });
-                search_stack.push(SearchParams { route: new_route });
+    // with S settled every pipe has at most two neighbours it's connected to both ways, so
+    // whether or not a walk comes back round, nothing it went through can be on another loop.
+    // Turning those tiles into ground means later walks stop as soon as they reach one, rather
+    // than going back over a whole chain of decoys.
+    let mut loops = Vec::new();
+    for y in 0..grid.len() {
+        for x in 0..grid[y].len() {
+            if grid[y][x] == Tile::Ground {
+                continue;
             }
-        }
-
-        if curr_tile.connects_east()
-            && curr_coords.x < grid_max_row_index
-            && grid[curr_coords.y][curr_coords.x + 1].connects_west()
-        {
-            if search_params.route.len() > 2
-                && grid[curr_coords.y][curr_coords.x + 1] == Tile::StartPipe
-            {
-                solution_route = Some(search_params.route);
-                break;
-            } else if !visited[curr_coords.y][curr_coords.x + 1] {
-                let mut new_route = search_params.route.clone();
-                new_route.push(Coords {
-                    y: curr_coords.y,
-                    x: curr_coords.x + 1,
-                });
-                search_stack.push(SearchParams { route: new_route });
+            let walked = match walk_loop(&grid, Coords { x, y }, grid[y][x]) {
+                Ok(pipe_loop) => {
+                    let walked = pipe_loop.vertices.clone();
+                    loops.push(pipe_loop);
+                    walked
+                }
+                Err(walked) => walked,
+            };
+            for coords in walked {
+                grid[coords.y][coords.x] = Tile::Ground;
             }
         }
     }
+    loops
+}
 
-    let solution_route_unwrapped = solution_route.unwrap();
-    let farthest_step = solution_route_unwrapped.len() / 2;
-    println!("Part 1 solution: {farthest_step}");
-
-    let main_loop_coords: HashSet<Coords> = HashSet::from_iter(solution_route_unwrapped);
-
-    // figure out what pipe type the StartPipe must actually be
-    let start_pipe_connects_west = starting_tile_coords.x > 0
-        && grid[starting_tile_coords.y][starting_tile_coords.x - 1].connects_east();
-    let start_pipe_connects_east = starting_tile_coords.x < grid_max_row_index
-        && grid[starting_tile_coords.y][starting_tile_coords.x + 1].connects_west();
-    let start_pipe_connects_north = starting_tile_coords.y > 0
-        && grid[starting_tile_coords.y - 1][starting_tile_coords.x].connects_south();
-    let start_pipe_connects_south = starting_tile_coords.y < grid_max_col_index
-        && grid[starting_tile_coords.y + 1][starting_tile_coords.x].connects_north();
-    let starting_tile_type = [
-        Tile::VerticalPipe,
-        Tile::HorizontalPipe,
-        Tile::NorthEastPipe,
-        Tile::NorthWestPipe,
-        Tile::SouthEastPipe,
-        Tile::SouthWestPipe,
-    ]
-    .iter()
-    .find(|tile_type| {
-        tile_type.connects_south() == start_pipe_connects_south
-            && tile_type.connects_north() == start_pipe_connects_north
-            && tile_type.connects_west() == start_pipe_connects_west
-            && tile_type.connects_east() == start_pipe_connects_east
-    })
-    .unwrap();
-    // substitute in the starting tile's real type
-    grid[starting_tile_coords.y][starting_tile_coords.x] = *starting_tile_type;
-
-    // set relevant accessibility for all main-loop pipe tiles
-    for coords in main_loop_coords.iter() {
-        accessibilities[coords.y][coords.x] = Some(TileAccessibility::MainLoopPipe);
+// The grid with S swapped for its real pipe type, and every tile that isn't part of the main
+// loop replaced with ground for ease of search.
+fn main_loop_grid(grid: &[Vec<Tile>], main_loop: &PipeLoop) -> Vec<Vec<Tile>> {
+    let mut loop_grid = grid
+        .iter()
+        .map(|row| vec![Tile::Ground; row.len()])
+        .collect::<Vec<_>>();
+    for coords in main_loop.vertices.iter() {
+        loop_grid[coords.y][coords.x] = match grid[coords.y][coords.x] {
+            Tile::StartPipe => main_loop.start_tile,
+            tile => tile,
+        };
     }
+    loop_grid
+}
 
-    // replace all freestanding pipe pieces with ground for ease of search
-    for y in 0..(grid_max_col_index + 1) {
-        for x in 0..(grid_max_row_index + 1) {
-            if !main_loop_coords.contains(&Coords { x, y }) {
-                grid[y][x] = Tile::Ground;
-            }
-        }
+// Works out whether each ground tile is enclosed by flood filling from it, squeezing between
+// pipes wherever they don't connect. Expects a grid from `main_loop_grid`.
+fn squeeze_flood_fill(grid: &[Vec<Tile>]) -> Vec<Vec<Option<TileAccessibility>>> {
+    if grid.first().is_none_or(Vec::is_empty) {
+        return grid.iter().map(|_| Vec::new()).collect();
     }
-
-    println!(
-        "{}",
-        display_grid_with_known_accessibilities(&grid, &accessibilities)
-    );
+    let grid_max_col_index = grid.len() - 1;
+    let grid_max_row_index = grid[0].len() - 1;
+    // every pipe left is part of the main loop
+    let mut accessibilities = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&tile| match tile {
+                    Tile::Ground => None,
+                    _ => Some(TileAccessibility::MainLoopPipe),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
     for y in 0..(grid_max_col_index + 1) {
         for x in 0..(grid_max_row_index + 1) {
             // we may have already determined this tile's type when looking ahead
-            if accessibilities[y][x].is_none() {
-                if grid[y][x] == Tile::Ground {
-                    // determine whether this and adjacent ground tiles are enclosed
-                    let mut current_search_connects_to_outside = false;
-                    let mut current_searched_ground_coords: HashSet<Coords> = HashSet::new();
-                    let mut current_searched_pipe_coords: HashSet<(Coords, Coords)> =
-                        HashSet::new();
-                    let mut search_stack = vec![CoordsMaybePipe {
-                        coords: Coords { x, y },
-                        additional_pipe_coords: None,
-                    }];
-                    while let Some(coords_maybe_pipe) = search_stack.pop() {
-                        let coords = coords_maybe_pipe.coords;
-                        let tile = grid[coords.y][coords.x];
-                        if tile == Tile::Ground {
-                            if current_searched_ground_coords.contains(&coords) {
-                                continue;
-                            }
-                            current_searched_ground_coords.insert(coords);
-                        } else {
-                            let other_pipe = coords_maybe_pipe.additional_pipe_coords.unwrap();
-                            if current_searched_pipe_coords.contains(&(coords, other_pipe))
-                                || current_searched_pipe_coords.contains(&(other_pipe, coords))
-                            {
-                                continue;
-                            }
-                            current_searched_pipe_coords.insert((coords, other_pipe));
+            if accessibilities[y][x].is_none() && grid[y][x] == Tile::Ground {
+                // determine whether this and adjacent ground tiles are enclosed
+                let mut current_search_connects_to_outside = false;
+                let mut current_searched_ground_coords: HashSet<Coords> = HashSet::new();
+                let mut current_searched_pipe_coords: HashSet<(Coords, Coords)> = HashSet::new();
+                let mut search_stack = vec![CoordsMaybePipe {
+                    coords: Coords { x, y },
+                    additional_pipe_coords: None,
+                }];
+                while let Some(coords_maybe_pipe) = search_stack.pop() {
+                    let coords = coords_maybe_pipe.coords;
+                    let tile = grid[coords.y][coords.x];
+                    if tile == Tile::Ground {
+                        if current_searched_ground_coords.contains(&coords) {
+                            continue;
+                        }
+                        current_searched_ground_coords.insert(coords);
+                    } else {
+                        let other_pipe = coords_maybe_pipe.additional_pipe_coords.unwrap();
+                        if current_searched_pipe_coords.contains(&(coords, other_pipe))
+                            || current_searched_pipe_coords.contains(&(other_pipe, coords))
+                        {
+                            continue;
                         }
+                        current_searched_pipe_coords.insert((coords, other_pipe));
+                    }
 
-                        let north: Option<(Coords, Tile)> = if coords.y > 0 {
-                            let coords = Coords {
-                                x: coords.x,
-                                y: coords.y - 1,
-                            };
-                            Some((coords, grid[coords.y][coords.x]))
-                        } else {
-                            None
+                    let north: Option<(Coords, Tile)> = if coords.y > 0 {
+                        let coords = Coords {
+                            x: coords.x,
+                            y: coords.y - 1,
                         };
-                        let northwest: Option<(Coords, Tile)> = if coords.y > 0 && coords.x > 0 {
-                            let coords = Coords {
-                                x: coords.x - 1,
-                                y: coords.y - 1,
-                            };
-                            Some((coords, grid[coords.y][coords.x]))
-                        } else {
-                            None
+                        Some((coords, grid[coords.y][coords.x]))
+                    } else {
+                        None
+                    };
+                    let northwest: Option<(Coords, Tile)> = if coords.y > 0 && coords.x > 0 {
+                        let coords = Coords {
+                            x: coords.x - 1,
+                            y: coords.y - 1,
                         };
-                        let northeast: Option<(Coords, Tile)> =
-                            if coords.y > 0 && coords.x < grid_max_row_index {
-                                let coords = Coords {
-                                    x: coords.x + 1,
-                                    y: coords.y - 1,
-                                };
-                                Some((coords, grid[coords.y][coords.x]))
-                            } else {
-                                None
-                            };
-                        let east: Option<(Coords, Tile)> = if coords.x < grid_max_row_index {
+                        Some((coords, grid[coords.y][coords.x]))
+                    } else {
+                        None
+                    };
+                    let northeast: Option<(Coords, Tile)> =
+                        if coords.y > 0 && coords.x < grid_max_row_index {
                             let coords = Coords {
                                 x: coords.x + 1,
-                                y: coords.y,
+                                y: coords.y - 1,
                             };
                             Some((coords, grid[coords.y][coords.x]))
                         } else {
                             None
                         };
-                        let west: Option<(Coords, Tile)> = if coords.x > 0 {
+                    let east: Option<(Coords, Tile)> = if coords.x < grid_max_row_index {
+                        let coords = Coords {
+                            x: coords.x + 1,
+                            y: coords.y,
+                        };
+                        Some((coords, grid[coords.y][coords.x]))
+                    } else {
+                        None
+                    };
+                    let west: Option<(Coords, Tile)> = if coords.x > 0 {
+                        let coords = Coords {
+                            x: coords.x - 1,
+                            y: coords.y,
+                        };
+                        Some((coords, grid[coords.y][coords.x]))
+                    } else {
+                        None
+                    };
+                    let south: Option<(Coords, Tile)> = if coords.y < grid_max_col_index {
+                        let coords = Coords {
+                            x: coords.x,
+                            y: coords.y + 1,
+                        };
+                        Some((coords, grid[coords.y][coords.x]))
+                    } else {
+                        None
+                    };
+                    let southwest: Option<(Coords, Tile)> =
+                        if coords.y < grid_max_col_index && coords.x > 0 {
                             let coords = Coords {
                                 x: coords.x - 1,
-                                y: coords.y,
+                                y: coords.y + 1,
                             };
                             Some((coords, grid[coords.y][coords.x]))
                         } else {
                             None
                         };
-                        let south: Option<(Coords, Tile)> = if coords.y < grid_max_col_index {
+                    let southeast: Option<(Coords, Tile)> =
+                        if coords.y < grid_max_col_index && coords.x < grid_max_row_index {
                             let coords = Coords {
-                                x: coords.x,
+                                x: coords.x + 1,
                                 y: coords.y + 1,
                             };
                             Some((coords, grid[coords.y][coords.x]))
                         } else {
                             None
                         };
-                        let southwest: Option<(Coords, Tile)> =
-                            if coords.y < grid_max_col_index && coords.x > 0 {
-                                let coords = Coords {
-                                    x: coords.x - 1,
-                                    y: coords.y + 1,
-                                };
-                                Some((coords, grid[coords.y][coords.x]))
-                            } else {
-                                None
-                            };
-                        let southeast: Option<(Coords, Tile)> =
-                            if coords.y < grid_max_col_index && coords.x < grid_max_row_index {
-                                let coords = Coords {
-                                    x: coords.x + 1,
-                                    y: coords.y + 1,
-                                };
-                                Some((coords, grid[coords.y][coords.x]))
-                            } else {
-                                None
-                            };
-                        let adjacent_tiles = vec![
-                            northwest, north, northeast, east, southeast, south, southwest, west,
-                        ];
-
-                        let defined_adjacent_tiles = adjacent_tiles
-                            .iter()
-                            .filter(|i| i.is_some())
-                            .map(|i| i.unwrap())
-                            .collect::<Vec<_>>();
-
-                        // starting from ground tile
-                        if tile == Tile::Ground {
-                            // first: detect whether we've reached the edge of the map
-                            if north.is_none()
-                                || south.is_none()
-                                || east.is_none()
-                                || west.is_none()
-                            {
-                                current_search_connects_to_outside = true;
-                            }
-                            // case 1: from ground tile to ground tile
-                            for adjacent in defined_adjacent_tiles {
-                                if adjacent.1 == Tile::Ground {
-                                    search_stack.push(CoordsMaybePipe {
-                                        coords: adjacent.0,
-                                        additional_pipe_coords: None,
-                                    })
-                                }
+                    let adjacent_tiles = [
+                        northwest, north, northeast, east, southeast, south, southwest, west,
+                    ];
+
+                    let defined_adjacent_tiles =
+                        adjacent_tiles.iter().flatten().collect::<Vec<_>>();
+
+                    // starting from ground tile
+                    if tile == Tile::Ground {
+                        // first: detect whether we've reached the edge of the map
+                        if north.is_none() || south.is_none() || east.is_none() || west.is_none() {
+                            current_search_connects_to_outside = true;
+                        }
+                        // case 1: from ground tile to ground tile
+                        for adjacent in defined_adjacent_tiles {
+                            if adjacent.1 == Tile::Ground {
+                                search_stack.push(CoordsMaybePipe {
+                                    coords: adjacent.0,
+                                    additional_pipe_coords: None,
+                                })
                             }
-                            // case 2: from ground tile to pipe gap
-                            for pair in adjacent_tiles.windows(2) {
-                                if allows_passage_between(pair[0], pair[1]) {
-                                    search_stack.push(CoordsMaybePipe {
-                                        coords: pair[0].unwrap().0,
-                                        additional_pipe_coords: Some(pair[1].unwrap().0),
-                                    })
-                                }
+                        }
+                        // case 2: from ground tile to pipe gap
+                        for pair in adjacent_tiles.windows(2) {
+                            if allows_passage_between(pair[0], pair[1]) {
+                                search_stack.push(CoordsMaybePipe {
+                                    coords: pair[0].unwrap().0,
+                                    additional_pipe_coords: Some(pair[1].unwrap().0),
+                                })
                             }
                         }
+                    }
 
-                        // starting from a pipe pair that we're actively squeezed between
-                        if tile != Tile::Ground {
-                            let paired_pipe_coords =
-                                coords_maybe_pipe.additional_pipe_coords.unwrap();
-                            let paired_pipe_tile = grid[paired_pipe_coords.y][paired_pipe_coords.x];
-                            let paired_pipe = (paired_pipe_coords, paired_pipe_tile);
-                            // first: detect whether we've reached the edge of the map
-                            if ((north.is_none() || south.is_none())
-                                && pipe_pair_allows_passage_north_or_south(
+                    // starting from a pipe pair that we're actively squeezed between
+                    if tile != Tile::Ground {
+                        let paired_pipe_coords = coords_maybe_pipe.additional_pipe_coords.unwrap();
+                        let paired_pipe_tile = grid[paired_pipe_coords.y][paired_pipe_coords.x];
+                        let paired_pipe = (paired_pipe_coords, paired_pipe_tile);
+                        // first: detect whether we've reached the edge of the map
+                        if ((north.is_none() || south.is_none())
+                            && pipe_pair_allows_passage_north_or_south((coords, tile), paired_pipe))
+                            || ((west.is_none() || east.is_none())
+                                && pipe_pair_allows_passage_east_or_west(
                                     (coords, tile),
                                     paired_pipe,
                                 ))
-                                || ((west.is_none() || east.is_none())
-                                    && pipe_pair_allows_passage_east_or_west(
-                                        (coords, tile),
-                                        paired_pipe,
-                                    ))
-                            {
-                                current_search_connects_to_outside = true;
+                        {
+                            current_search_connects_to_outside = true;
+                        }
+                        let direction_of_adj_pipe_from_main =
+                            direction_of_second_from_first(coords, paired_pipe_coords);
+                        if pipe_pair_allows_passage_north_or_south((coords, tile), paired_pipe) {
+                            // case 3a: from pipe pair to ground tiles, north-south
+                            let mut eligible_adjacents = vec![north, south];
+                            if direction_of_adj_pipe_from_main == Direction::East {
+                                eligible_adjacents.push(northeast);
+                                eligible_adjacents.push(southeast);
+                            } else if direction_of_adj_pipe_from_main == Direction::West {
+                                eligible_adjacents.push(northwest);
+                                eligible_adjacents.push(southwest);
+                            }
+                            let eligible_adj_ground_coords = eligible_adjacents
+                                .iter()
+                                .filter(|maybe_tup| {
+                                    maybe_tup.is_some() && maybe_tup.unwrap().1 == Tile::Ground
+                                })
+                                .map(|maybe_tup| maybe_tup.unwrap().0)
+                                .collect::<Vec<_>>();
+                            for adj_ground_coords in eligible_adj_ground_coords {
+                                search_stack.push(CoordsMaybePipe {
+                                    coords: adj_ground_coords,
+                                    additional_pipe_coords: None,
+                                });
                             }
-                            let direction_of_adj_pipe_from_main =
-                                direction_of_second_from_first(coords, paired_pipe_coords);
-                            if pipe_pair_allows_passage_north_or_south((coords, tile), paired_pipe)
-                            {
-                                // case 3a: from pipe pair to ground tiles, north-south
-                                let mut eligible_adjacents = vec![north, south];
+                            // case 4a: from pipe pair to pipe pair, originating north-south
+                            let possible_adj_pipe_pairs =
                                 if direction_of_adj_pipe_from_main == Direction::East {
-                                    eligible_adjacents.push(northeast);
-                                    eligible_adjacents.push(southeast);
+                                    vec![
+                                        (north, northeast),
+                                        (Some((coords, tile)), north), // 90-degree turn
+                                        (northeast, Some(paired_pipe)), // 90-degree turn
+                                        (south, southeast),
+                                        (Some((coords, tile)), south), // 90-degree turn
+                                        (southeast, Some(paired_pipe)), // 90-degree turn
+                                    ]
                                 } else if direction_of_adj_pipe_from_main == Direction::West {
-                                    eligible_adjacents.push(northwest);
-                                    eligible_adjacents.push(southwest);
-                                }
-                                let eligible_adj_ground_coords = eligible_adjacents
-                                    .iter()
-                                    .filter(|maybe_tup| {
-                                        maybe_tup.is_some() && maybe_tup.unwrap().1 == Tile::Ground
-                                    })
-                                    .map(|maybe_tup| maybe_tup.unwrap().0)
-                                    .collect::<Vec<_>>();
-                                for adj_ground_coords in eligible_adj_ground_coords {
-                                    search_stack.push(CoordsMaybePipe {
-                                        coords: adj_ground_coords,
-                                        additional_pipe_coords: None,
-                                    });
-                                }
-                                // case 4a: from pipe pair to pipe pair, originating north-south
-                                let possible_adj_pipe_pairs =
-                                    if direction_of_adj_pipe_from_main == Direction::East {
-                                        vec![
-                                            (north, northeast),
-                                            (Some((coords, tile)), north), // 90-degree turn
-                                            (northeast, Some(paired_pipe)), // 90-degree turn
-                                            (south, southeast),
-                                            (Some((coords, tile)), south), // 90-degree turn
-                                            (southeast, Some(paired_pipe)), // 90-degree turn
-                                        ]
-                                    } else if direction_of_adj_pipe_from_main == Direction::West {
-                                        vec![
-                                            (northwest, north),
-                                            (Some(paired_pipe), northwest), // 90-degree turn
-                                            (Some((coords, tile)), north),  // 90-degree turn
-                                            (southwest, south),
-                                            (Some(paired_pipe), southwest), // 90-degree turn
-                                            (Some((coords, tile)), south),  // 90-degree turn
-                                        ]
-                                    } else {
-                                        Vec::new()
-                                    };
-                                let filtered_pairs =
-                                    possible_adj_pipe_pairs.iter().filter(|(first, second)| {
-                                        allows_passage_between(*first, *second)
-                                    });
-                                for pair in filtered_pairs {
-                                    search_stack.push(CoordsMaybePipe {
-                                        coords: pair.0.unwrap().0,
-                                        additional_pipe_coords: Some(pair.1.unwrap().0),
-                                    })
-                                }
+                                    vec![
+                                        (northwest, north),
+                                        (Some(paired_pipe), northwest), // 90-degree turn
+                                        (Some((coords, tile)), north),  // 90-degree turn
+                                        (southwest, south),
+                                        (Some(paired_pipe), southwest), // 90-degree turn
+                                        (Some((coords, tile)), south),  // 90-degree turn
+                                    ]
+                                } else {
+                                    Vec::new()
+                                };
+                            let filtered_pairs = possible_adj_pipe_pairs
+                                .iter()
+                                .filter(|(first, second)| allows_passage_between(*first, *second));
+                            for pair in filtered_pairs {
+                                search_stack.push(CoordsMaybePipe {
+                                    coords: pair.0.unwrap().0,
+                                    additional_pipe_coords: Some(pair.1.unwrap().0),
+                                })
                             }
-                            if pipe_pair_allows_passage_east_or_west((coords, tile), paired_pipe) {
-                                // case 3b: from pipe pair to ground tiles, east-west
-                                let mut eligible_adjacents = vec![east, west];
+                        }
+                        if pipe_pair_allows_passage_east_or_west((coords, tile), paired_pipe) {
+                            // case 3b: from pipe pair to ground tiles, east-west
+                            let mut eligible_adjacents = vec![east, west];
+                            if direction_of_adj_pipe_from_main == Direction::North {
+                                eligible_adjacents.push(northeast);
+                                eligible_adjacents.push(northwest);
+                            } else if direction_of_adj_pipe_from_main == Direction::South {
+                                eligible_adjacents.push(southeast);
+                                eligible_adjacents.push(southwest);
+                            }
+                            let eligible_adj_ground_coords = eligible_adjacents
+                                .iter()
+                                .filter(|maybe_tup| {
+                                    maybe_tup.is_some() && maybe_tup.unwrap().1 == Tile::Ground
+                                })
+                                .map(|maybe_tup| maybe_tup.unwrap().0)
+                                .collect::<Vec<_>>();
+                            for adj_ground_coords in eligible_adj_ground_coords {
+                                search_stack.push(CoordsMaybePipe {
+                                    coords: adj_ground_coords,
+                                    additional_pipe_coords: None,
+                                });
+                            }
+                            // case 4b: from pipe pair to pipe pair, originating east-west
+                            let possible_adj_pipe_pairs =
                                 if direction_of_adj_pipe_from_main == Direction::North {
-                                    eligible_adjacents.push(northeast);
-                                    eligible_adjacents.push(northwest);
+                                    vec![
+                                        (east, northeast),
+                                        (Some((coords, tile)), east), // 90-degree turn
+                                        (Some(paired_pipe), northeast), // 90-degree turn
+                                        (west, northwest),
+                                        (Some((coords, tile)), west), // 90-degree turn
+                                        (Some(paired_pipe), northwest), // 90-degree turn
+                                    ]
                                 } else if direction_of_adj_pipe_from_main == Direction::South {
-                                    eligible_adjacents.push(southeast);
-                                    eligible_adjacents.push(southwest);
-                                }
-                                let eligible_adj_ground_coords = eligible_adjacents
-                                    .iter()
-                                    .filter(|maybe_tup| {
-                                        maybe_tup.is_some() && maybe_tup.unwrap().1 == Tile::Ground
-                                    })
-                                    .map(|maybe_tup| maybe_tup.unwrap().0)
-                                    .collect::<Vec<_>>();
-                                for adj_ground_coords in eligible_adj_ground_coords {
-                                    search_stack.push(CoordsMaybePipe {
-                                        coords: adj_ground_coords,
-                                        additional_pipe_coords: None,
-                                    });
-                                }
-                                // case 4b: from pipe pair to pipe pair, originating east-west
-                                let possible_adj_pipe_pairs =
-                                    if direction_of_adj_pipe_from_main == Direction::North {
-                                        vec![
-                                            (east, northeast),
-                                            (Some((coords, tile)), east), // 90-degree turn
-                                            (Some(paired_pipe), northeast), // 90-degree turn
-                                            (west, northwest),
-                                            (Some((coords, tile)), west), // 90-degree turn
-                                            (Some(paired_pipe), northwest), // 90-degree turn
-                                        ]
-                                    } else if direction_of_adj_pipe_from_main == Direction::South {
-                                        vec![
-                                            (east, southeast),
-                                            (Some((coords, tile)), east), // 90-degree turn
-                                            (Some(paired_pipe), southeast), // 90-degree turn
-                                            (west, southwest),
-                                            (Some((coords, tile)), west), // 90-degree turn
-                                            (Some(paired_pipe), southwest), // 90-degree turn
-                                        ]
-                                    } else {
-                                        Vec::new()
-                                    };
-                                let filtered_pairs =
-                                    possible_adj_pipe_pairs.iter().filter(|(first, second)| {
-                                        allows_passage_between(*first, *second)
-                                    });
-                                for pair in filtered_pairs {
-                                    search_stack.push(CoordsMaybePipe {
-                                        coords: pair.0.unwrap().0,
-                                        additional_pipe_coords: Some(pair.1.unwrap().0),
-                                    })
-                                }
+                                    vec![
+                                        (east, southeast),
+                                        (Some((coords, tile)), east), // 90-degree turn
+                                        (Some(paired_pipe), southeast), // 90-degree turn
+                                        (west, southwest),
+                                        (Some((coords, tile)), west), // 90-degree turn
+                                        (Some(paired_pipe), southwest), // 90-degree turn
+                                    ]
+                                } else {
+                                    Vec::new()
+                                };
+                            let filtered_pairs = possible_adj_pipe_pairs
+                                .iter()
+                                .filter(|(first, second)| allows_passage_between(*first, *second));
+                            for pair in filtered_pairs {
+                                search_stack.push(CoordsMaybePipe {
+                                    coords: pair.0.unwrap().0,
+                                    additional_pipe_coords: Some(pair.1.unwrap().0),
+                                })
                             }
                         }
                     }
+                }
 
-                    // Update 'accessibilities' grid based on search results
-                    let searched_ground_type = if current_search_connects_to_outside {
-                        TileAccessibility::UnenclosedGround
-                    } else {
-                        TileAccessibility::MainLoopEnclosedGround
-                    };
-                    for coords in current_searched_ground_coords {
-                        accessibilities[coords.y][coords.x] = Some(searched_ground_type);
-                    }
+                // Update 'accessibilities' grid based on search results
+                let searched_ground_type = if current_search_connects_to_outside {
+                    TileAccessibility::UnenclosedGround
+                } else {
+                    TileAccessibility::MainLoopEnclosedGround
+                };
+                for coords in current_searched_ground_coords {
+                    accessibilities[coords.y][coords.x] = Some(searched_ground_type);
                 }
             }
         }
     }
+    accessibilities
+}
 
-    println!(
-        "{}",
-        display_grid_with_known_accessibilities(&grid, &accessibilities)
-    );
-
-    let num_main_loop_enclosed_ground_tiles = accessibilities
+fn count_enclosed(accessibilities: &[Vec<Option<TileAccessibility>>]) -> usize {
+    accessibilities
         .iter()
         .map(|row| {
             row.iter()
                 .filter(|acc| **acc == Some(TileAccessibility::MainLoopEnclosedGround))
                 .count()
         })
-        .sum::<usize>();
+        .sum::<usize>()
+}
+
+// Pick's theorem says a polygon with its corners on the grid has area A = i + b / 2 - 1, where
+// i counts the grid points strictly inside it and b the ones on its boundary. Every tile of the
+// loop is a boundary point, and the shoelace formula gives A from the loop in order, so
+// i = A - b / 2 + 1.
+fn count_enclosed_by_pick(main_loop: &[Coords]) -> usize {
+    let twice_area = twice_signed_area(main_loop).abs();
+    ((twice_area - main_loop.len() as i64 + 2) / 2) as usize
+}
+
+// The shoelace formula, left signed so it also tells us which way round the loop goes.
+fn twice_signed_area(vertices: &[Coords]) -> i64 {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64)
+        .sum()
+}
+
+// Sweeps each row from west to east, flipping between outside and inside whenever we cross the
+// loop. Only pipes that connect north count as crossings, which handles running along the loop:
+// F--J crosses it once, while F--7 only touches it. Expects a grid from `main_loop_grid`.
+fn count_enclosed_by_scanline(grid: &[Vec<Tile>]) -> usize {
+    let mut num_enclosed = 0;
+    for row in grid.iter() {
+        let mut inside = false;
+        for tile in row.iter() {
+            if *tile == Tile::Ground {
+                if inside {
+                    num_enclosed += 1;
+                }
+            } else if tile.connects_north() {
+                inside = !inside;
+            }
+        }
+    }
+    num_enclosed
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    // `--generate <path> <width> <height> [<seed> [decoys]]` writes out a random maze, with
+    // pipes that aren't part of the loop scattered around if asked for
+    if args.len() > 4 && args[1] == "--generate" {
+        let (width, height) = (args[3].parse().unwrap(), args[4].parse().unwrap());
+        let mut rng = Rng::new(args.get(5).map_or(1, |seed| seed.parse().unwrap()));
+        let decoys = args.get(6).is_some_and(|arg| arg == "decoys");
+        let generated = generate_loop(&mut rng, width, height, decoys);
+        fs::write(&args[2], generated.text).unwrap();
+        println!(
+            "Wrote {width} × {height} maze to {}, whose loop is {} tiles long and encloses {}",
+            args[2], generated.loop_len, generated.num_enclosed
+        );
+        return;
+    }
+    let file_path = match args.get(1).map(String::as_str) {
+        Some("--unicode") | Some("--loops") => args.get(2),
+        Some("--svg") => args.get(3),
+        _ => args.get(1),
+    }
+    .map_or("resources/input_1", |s| s.as_str());
+    let grid = parse_grid(&fs::read_to_string(file_path).unwrap());
+
+    let starting_tile_coords = find_start(&grid);
+
+    // `--loops [<file>]` lists every closed loop in the grid and how much each encloses
+    if args.len() > 1 && args[1] == "--loops" {
+        let loops = find_all_loops(&grid);
+        println!("Found {} loops", loops.len());
+        for (i, pipe_loop) in loops.iter().enumerate() {
+            let first = pipe_loop.vertices[0];
+            let through_start = if starting_tile_coords
+                .is_some_and(|coords| pipe_loop.vertices.contains(&coords))
+            {
+                " through S"
+            } else {
+                ""
+            };
+            println!(
+                "Loop {}{through_start}: {} tiles from ({}, {}), running {:?}, enclosing {}",
+                i + 1,
+                pipe_loop.vertices.len(),
+                first.x,
+                first.y,
+                pipe_loop.orientation,
+                count_enclosed_by_pick(&pipe_loop.vertices)
+            );
+        }
+        return;
+    }
+
+    let Some(starting_tile_coords) = starting_tile_coords else {
+        println!("Error: there's no S in {file_path}");
+        return;
+    };
+    let main_loop = match find_main_loop(&grid, starting_tile_coords) {
+        Ok(main_loop) => main_loop,
+        Err(err) => {
+            println!("Error: {err}");
+            return;
+        }
+    };
+    let loop_grid = main_loop_grid(&grid, &main_loop);
+    let accessibilities = squeeze_flood_fill(&loop_grid);
+
+    // `--unicode [<file>]` draws the map in colour with box-drawing characters
+    if args.len() > 1 && args[1] == "--unicode" {
+        print!(
+            "{}",
+            render_unicode(&grid, &main_loop, &accessibilities, true)
+        );
+        return;
+    }
+    // `--svg <path> [<file>]` writes the map out as an SVG image
+    if args.len() > 2 && args[1] == "--svg" {
+        fs::write(&args[2], render_svg(&grid, &main_loop, &accessibilities)).unwrap();
+        println!("Wrote map to {}", args[2]);
+        return;
+    }
+
+    let farthest_step = main_loop.vertices.len() / 2;
+    println!("Part 1 solution: {farthest_step}");
+    println!(
+        "Main loop runs {:?} from S, which is a '{}'",
+        main_loop.orientation,
+        main_loop.start_tile.to_char()
+    );
+    println!(
+        "{}",
+        display_grid_with_known_accessibilities(&loop_grid, &accessibilities)
+    );
+
+    let num_main_loop_enclosed_ground_tiles = count_enclosed(&accessibilities);
     println!("Part 2 solution: {num_main_loop_enclosed_ground_tiles}");
+    println!(
+        "Part 2 solution (shoelace and Pick's theorem): {}",
+        count_enclosed_by_pick(&main_loop.vertices)
+    );
+    println!(
+        "Part 2 solution (scanline parity): {}",
+        count_enclosed_by_scanline(&loop_grid)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Part 2 from all three solvers, checking that they agree.
+    fn solve_part_2(grid: &[Vec<Tile>]) -> usize {
+        let main_loop = find_main_loop(grid, find_start(grid).unwrap()).unwrap();
+        let loop_grid = main_loop_grid(grid, &main_loop);
+        let num_enclosed = count_enclosed(&squeeze_flood_fill(&loop_grid));
+        assert_eq!(count_enclosed_by_pick(&main_loop.vertices), num_enclosed);
+        assert_eq!(count_enclosed_by_scanline(&loop_grid), num_enclosed);
+        num_enclosed
+    }
+
+    #[test]
+    fn test_samples() {
+        let expected = [(8, 1), (23, 4), (22, 4), (70, 8), (80, 10)];
+        for (i, (farthest_step, num_enclosed)) in expected.into_iter().enumerate() {
+            let file_content = fs::read_to_string(format!("resources/sample_{}", i + 1)).unwrap();
+            let grid = parse_grid(&file_content);
+            assert_eq!(
+                find_main_loop(&grid, find_start(&grid).unwrap())
+                    .unwrap()
+                    .vertices
+                    .len()
+                    / 2,
+                farthest_step
+            );
+            assert_eq!(solve_part_2(&grid), num_enclosed);
+        }
+    }
+
+    #[test]
+    fn test_input() {
+        let grid = parse_grid(&fs::read_to_string("resources/input_1").unwrap());
+        assert_eq!(
+            find_main_loop(&grid, find_start(&grid).unwrap())
+                .unwrap()
+                .vertices
+                .len()
+                / 2,
+            6815
+        );
+        assert_eq!(solve_part_2(&grid), 269);
+    }
+
+    #[test]
+    fn test_loop_orientation_and_start_tile() {
+        let file_content = fs::read_to_string("resources/sample_1").unwrap();
+        let grid = parse_grid(&file_content);
+        let main_loop = find_main_loop(&grid, find_start(&grid).unwrap()).unwrap();
+        assert_eq!(main_loop.start_tile, Tile::SouthEastPipe);
+        // S heads south first, down the west edge
+        assert_eq!(main_loop.orientation, Orientation::CounterClockwise);
+        assert_eq!(main_loop.vertices[1], Coords { x: 0, y: 3 });
+
+        // mirrored east to west, S still heads south first but now down the east edge
+        let mirrored = file_content
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .rev()
+                    .map(|c| match c {
+                        'F' => '7',
+                        '7' => 'F',
+                        'L' => 'J',
+                        'J' => 'L',
+                        c => c,
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let grid = parse_grid(&mirrored);
+        let main_loop = find_main_loop(&grid, find_start(&grid).unwrap()).unwrap();
+        assert_eq!(main_loop.start_tile, Tile::SouthWestPipe);
+        assert_eq!(main_loop.orientation, Orientation::Clockwise);
+        assert_eq!(main_loop.vertices.len(), 16);
+    }
+
+    #[test]
+    fn test_ambiguous_start() {
+        // S has pipes pointing at it from the north, south and west, but only the north and
+        // south ones lead back round to it
+        let grid = parse_grid(
+            "\
+.....
+.F-7.
+-S.|.
+.L-J.
+.....
+",
+        );
+        let start = find_start(&grid).unwrap();
+        assert_eq!(
+            start_tile_candidates(&grid, start),
+            [Tile::VerticalPipe, Tile::NorthWestPipe, Tile::SouthWestPipe]
+        );
+        let main_loop = find_main_loop(&grid, start).unwrap();
+        assert_eq!(main_loop.start_tile, Tile::VerticalPipe);
+        assert_eq!(main_loop.vertices.len(), 8);
+        assert_eq!(solve_part_2(&grid), 1);
+
+        // with nothing leading back round to S there's no main loop at all
+        let grid = parse_grid(".|.\n-S-\n...\n");
+        assert_eq!(
+            find_main_loop(&grid, find_start(&grid).unwrap()).unwrap_err(),
+            LoopError::NoLoopThroughStart {
+                start: Coords { x: 1, y: 1 }
+            }
+        );
+        assert!(find_all_loops(&grid).is_empty());
+    }
+
+    #[test]
+    fn test_all_loops() {
+        let grid = parse_grid(
+            "\
+F7.F---7..
+LJ.|F7.|F7
+.F-S||.|LJ
+.|.LJL-J..
+.L--7.-7..
+F7..J.F7..
+|L7...LJ..
+L-J.......
+",
+        );
+        let loops = find_all_loops(&grid);
+        let summaries = loops
+            .iter()
+            .map(|pipe_loop| {
+                (
+                    pipe_loop.vertices[0],
+                    pipe_loop.vertices.len(),
+                    count_enclosed_by_pick(&pipe_loop.vertices),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summaries,
+            [
+                (Coords { x: 0, y: 0 }, 4, 0),
+                (Coords { x: 3, y: 0 }, 18, 2),
+                (Coords { x: 8, y: 1 }, 4, 0),
+                (Coords { x: 0, y: 5 }, 8, 0),
+                (Coords { x: 6, y: 5 }, 4, 0),
+            ]
+        );
+        // S could also join up with the pipe to the west, but that one's a dead end, so only a |
+        // puts it on a loop
+        let start = find_start(&grid).unwrap();
+        let main_loop = find_main_loop(&grid, start).unwrap();
+        assert_eq!(main_loop.start_tile, Tile::VerticalPipe);
+        assert!(loops[1].vertices.contains(&start));
+    }
+
+    #[test]
+    fn test_find_all_loops_without_start() {
+        let grid = parse_grid(
+            "\
+F7.F-7
+LJ.|.|
+...L-J
+",
+        );
+        assert_eq!(find_start(&grid), None);
+        let loops = find_all_loops(&grid);
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].vertices[0], Coords { x: 0, y: 0 });
+        assert_eq!(loops[1].vertices.len(), 8);
+        assert_eq!(count_enclosed_by_pick(&loops[1].vertices), 1);
+    }
+
+    #[test]
+    fn test_find_all_loops_edge_cases() {
+        let grid = parse_grid("");
+        assert!(find_all_loops(&grid).is_empty());
+        assert!(squeeze_flood_fill(&grid).is_empty());
+
+        // a long row of pipes that don't close up, each of which gets walked from once
+        let mut file_content = "-".repeat(50_000);
+        file_content.push_str("\nF7\nLJ\n");
+        let loops = find_all_loops(&parse_grid(&file_content));
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].vertices[0], Coords { x: 0, y: 1 });
+    }
 }
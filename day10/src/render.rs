@@ -0,0 +1,306 @@
+use super::{Coords, PipeLoop, Tile, TileAccessibility};
+use std::fmt::Write;
+
+// ANSI escapes for the terminal
+const LOOP_COLOUR: &str = "\x1b[1;33m";
+const START_COLOUR: &str = "\x1b[1;31m";
+const INSIDE_COLOUR: &str = "\x1b[32m";
+const OUTSIDE_COLOUR: &str = "\x1b[2;34m";
+const RESET_COLOUR: &str = "\x1b[0m";
+
+// SVG units per tile
+const TILE_SIZE: usize = 10;
+
+impl Tile {
+    fn to_box_char(self) -> char {
+        use Tile::*;
+        match self {
+            Ground => '·',
+            StartPipe => 'S',
+            VerticalPipe => '│',
+            HorizontalPipe => '─',
+            NorthEastPipe => '└',
+            NorthWestPipe => '┘',
+            SouthWestPipe => '┐',
+            SouthEastPipe => '┌',
+        }
+    }
+}
+
+// Whether a tile is on the main loop, inside it or outside it. Pipes that aren't part of the main
+// loop count as ground here, same as in `main_loop_grid`.
+fn region(accessibilities: &[Vec<Option<TileAccessibility>>], coords: Coords) -> TileAccessibility {
+    accessibilities[coords.y][coords.x].expect("Every tile should be classified by now")
+}
+
+// The tile as it's drawn, which for S is the pipe it really is.
+fn drawn_tile(grid: &[Vec<Tile>], main_loop: &PipeLoop, coords: Coords) -> Tile {
+    match grid[coords.y][coords.x] {
+        Tile::StartPipe => main_loop.start_tile,
+        tile => tile,
+    }
+}
+
+pub fn display_grid_with_known_accessibilities(
+    grid: &[Vec<Tile>],
+    accessibilities: &[Vec<Option<TileAccessibility>>],
+) -> String {
+    let mut printable = String::with_capacity((grid[0].len() + 1) * grid.len() + 1);
+    for (y, row) in grid.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            printable.push(match (tile, accessibilities[y][x]) {
+                (Tile::Ground, Some(TileAccessibility::UnenclosedGround)) => 'O',
+                (Tile::Ground, Some(TileAccessibility::MainLoopEnclosedGround)) => 'I',
+                _ => tile.to_char(),
+            });
+        }
+        printable.push('\n');
+    }
+    printable.push('\n');
+    printable
+}
+
+// Draws the grid with box-drawing characters. Ground is drawn as `▒` inside the main loop and
+// `·` outside it, and with `colour` the main loop, S, and the tiles inside and outside it each
+// get their own colour. Any pipes that aren't part of the main loop are coloured by which side of
+// it they're on.
+pub fn render_unicode(
+    grid: &[Vec<Tile>],
+    main_loop: &PipeLoop,
+    accessibilities: &[Vec<Option<TileAccessibility>>],
+    colour: bool,
+) -> String {
+    let mut rendered = String::new();
+    for (y, row) in grid.iter().enumerate() {
+        let mut curr_colour = RESET_COLOUR;
+        for x in 0..row.len() {
+            let coords = Coords { x, y };
+            let tile = drawn_tile(grid, main_loop, coords);
+            let (tile_colour, c) = match region(accessibilities, coords) {
+                TileAccessibility::MainLoopPipe if coords == main_loop.vertices[0] => {
+                    (START_COLOUR, tile.to_box_char())
+                }
+                TileAccessibility::MainLoopPipe => (LOOP_COLOUR, tile.to_box_char()),
+                TileAccessibility::MainLoopEnclosedGround if tile == Tile::Ground => {
+                    (INSIDE_COLOUR, '▒')
+                }
+                TileAccessibility::MainLoopEnclosedGround => (INSIDE_COLOUR, tile.to_box_char()),
+                TileAccessibility::UnenclosedGround => (OUTSIDE_COLOUR, tile.to_box_char()),
+            };
+            // only switch colour when it changes, to keep the output small
+            if colour && tile_colour != curr_colour {
+                rendered.push_str(RESET_COLOUR);
+                rendered.push_str(tile_colour);
+                curr_colour = tile_colour;
+            }
+            rendered.push(c);
+        }
+        if colour {
+            rendered.push_str(RESET_COLOUR);
+        }
+        rendered.push('\n');
+    }
+    rendered
+}
+
+// Draws the grid as an SVG image, with the tiles inside and outside the main loop shaded and
+// the main loop drawn over the top through the middle of its tiles, starting from a dot at S.
+pub fn render_svg(
+    grid: &[Vec<Tile>],
+    main_loop: &PipeLoop,
+    accessibilities: &[Vec<Option<TileAccessibility>>],
+) -> String {
+    let (width, height) = (grid[0].len() * TILE_SIZE, grid.len() * TILE_SIZE);
+    let centre = |n: usize| n * TILE_SIZE + TILE_SIZE / 2;
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r##"<rect width="{width}" height="{height}" fill="#ffffff"/>"##
+    )
+    .unwrap();
+
+    // shade each run of inside or outside tiles along a row with a single rectangle
+    for (y, row) in grid.iter().enumerate() {
+        let mut x = 0;
+        while x < row.len() {
+            let run_region = region(accessibilities, Coords { x, y });
+            let run_len = (x..row.len())
+                .take_while(|&x| region(accessibilities, Coords { x, y }) == run_region)
+                .count();
+            let fill = match run_region {
+                TileAccessibility::MainLoopEnclosedGround => Some("#9be39b"),
+                TileAccessibility::UnenclosedGround => Some("#dde6f3"),
+                TileAccessibility::MainLoopPipe => None,
+            };
+            if let Some(fill) = fill {
+                writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{TILE_SIZE}" fill="{fill}"/>"#,
+                    x * TILE_SIZE,
+                    y * TILE_SIZE,
+                    run_len * TILE_SIZE
+                )
+                .unwrap();
+            }
+            x += run_len;
+        }
+    }
+
+    // pipes that aren't part of the main loop, each as a line from one connection through the
+    // middle of the tile to the other
+    let mut other_pipes = String::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &tile) in row.iter().enumerate() {
+            let coords = Coords { x, y };
+            if tile == Tile::Ground
+                || region(accessibilities, coords) == TileAccessibility::MainLoopPipe
+            {
+                continue;
+            }
+            let (cx, cy) = (centre(x) as isize, centre(y) as isize);
+            let half = (TILE_SIZE / 2) as isize;
+            let ends = [
+                (tile.connects_north(), (cx, cy - half)),
+                (tile.connects_south(), (cx, cy + half)),
+                (tile.connects_east(), (cx + half, cy)),
+                (tile.connects_west(), (cx - half, cy)),
+            ]
+            .into_iter()
+            .filter(|&(connects, _)| connects)
+            .map(|(_, end)| end)
+            .collect::<Vec<_>>();
+            if let [(x1, y1), (x2, y2)] = ends[..] {
+                write!(other_pipes, "M{x1} {y1}L{cx} {cy}L{x2} {y2}").unwrap();
+            }
+        }
+    }
+    if !other_pipes.is_empty() {
+        writeln!(
+            svg,
+            r##"<path d="{other_pipes}" fill="none" stroke="#8a8a8a" stroke-width="1.5"/>"##
+        )
+        .unwrap();
+    }
+
+    let mut loop_path = String::new();
+    for (i, coords) in main_loop.vertices.iter().enumerate() {
+        let command = if i == 0 { 'M' } else { 'L' };
+        write!(
+            loop_path,
+            "{command}{} {}",
+            centre(coords.x),
+            centre(coords.y)
+        )
+        .unwrap();
+    }
+    writeln!(
+        svg,
+        r##"<path d="{loop_path}Z" fill="none" stroke="#c48a00" stroke-width="3" stroke-linejoin="round"/>"##
+    )
+    .unwrap();
+    let start = main_loop.vertices[0];
+    writeln!(
+        svg,
+        r##"<circle cx="{}" cy="{}" r="{}" fill="#d62828"/>"##,
+        centre(start.x),
+        centre(start.y),
+        TILE_SIZE / 3
+    )
+    .unwrap();
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        find_main_loop, find_start, main_loop_grid, parse_grid, squeeze_flood_fill,
+    };
+    use super::*;
+
+    fn render_sample(file_content: &str, colour: bool) -> (String, String) {
+        let grid = parse_grid(file_content);
+        let main_loop = find_main_loop(&grid, find_start(&grid).unwrap()).unwrap();
+        let accessibilities = squeeze_flood_fill(&main_loop_grid(&grid, &main_loop));
+        (
+            render_unicode(&grid, &main_loop, &accessibilities, colour),
+            render_svg(&grid, &main_loop, &accessibilities),
+        )
+    }
+
+    #[test]
+    fn test_render_unicode() {
+        let sample = "\
+..........
+.S------7.
+.|F----7|.
+.||.F7.||.
+.||.LJ.||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+..........
+";
+        let (rendered, _) = render_sample(sample, false);
+        let expected = "\
+··········
+·┌──────┐·
+·│┌────┐│·
+·││·┌┐·││·
+·││·└┘·││·
+·│└─┐┌─┘│·
+·│▒▒││▒▒│·
+·└──┘└──┘·
+··········
+";
+        assert_eq!(rendered, expected);
+
+        let (coloured, _) = render_sample(sample, true);
+        assert!(coloured.contains(&format!(
+            "{START_COLOUR}┌{RESET_COLOUR}{LOOP_COLOUR}──────┐"
+        )));
+        assert!(coloured.contains(&format!("{INSIDE_COLOUR}▒▒")));
+        // stripping the colours leaves the plain rendering
+        let mut stripped = coloured.clone();
+        for escape in [
+            LOOP_COLOUR,
+            START_COLOUR,
+            INSIDE_COLOUR,
+            OUTSIDE_COLOUR,
+            RESET_COLOUR,
+        ] {
+            stripped = stripped.replace(escape, "");
+        }
+        assert_eq!(stripped, expected);
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let file_content = std::fs::read_to_string("resources/sample_5").unwrap();
+        let (_, svg) = render_sample(&file_content, false);
+        assert!(
+            svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100""#)
+        );
+        assert!(svg.ends_with("</svg>\n"));
+        // one loop path, plus one for the pipes that aren't on it, and a dot for S
+        assert_eq!(svg.matches("<path ").count(), 2);
+        assert_eq!(svg.matches("<circle ").count(), 1);
+        // S is at (4, 0), so the loop starts in the middle of that tile
+        assert!(svg.contains(r#"<path d="M45 5L"#));
+        // every tile in the ten enclosed ones is shaded
+        let inside_width = svg
+            .lines()
+            .filter(|line| line.contains("#9be39b"))
+            .map(|line| {
+                let width = line.split(r#"width=""#).nth(1).unwrap();
+                width[..width.find('"').unwrap()].parse::<usize>().unwrap()
+            })
+            .sum::<usize>();
+        assert_eq!(inside_width, 10 * TILE_SIZE);
+    }
+}